@@ -0,0 +1,56 @@
+//! Compares resolving a batch of raw values at once, borrowing the names, with
+//! formatting them one by one, which allocates a string per value. Run with
+//! `cargo bench`.
+
+use std::{collections::BTreeMap, hint::black_box, time::Instant};
+
+use drdump::{Base, DropReasons};
+
+const ITERATIONS: usize = 100;
+
+fn reasons() -> DropReasons {
+    let mut reasons: BTreeMap<u32, String> = (0..128)
+        .map(|val| (val, format!("SKB_DROP_REASON_CORE_{val}")))
+        .collect();
+    reasons.extend((0..32).map(|i| (0x30000 | i, format!("OVS_DROP_{i}"))));
+    let subsys = BTreeMap::from([
+        (0, "SKB_DROP_REASON_SUBSYS_CORE".to_string()),
+        (3, "SKB_DROP_REASON_SUBSYS_OPENVSWITCH".to_string()),
+    ]);
+    DropReasons::new(reasons, Some(subsys))
+}
+
+// Runs f ITERATIONS times, after a warm up run, and reports the average time
+// per run.
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    println!(
+        "{name:<16} {:>10.2?}/iter",
+        start.elapsed() / ITERATIONS as u32
+    );
+}
+
+fn main() {
+    let reasons = reasons();
+    // Mimic a histogram of drops: mostly known values, some unknown ones.
+    let values: Vec<u32> = (0..10_000u32)
+        .map(|i| match i % 10 {
+            0 => 0x30000 | (i % 64),
+            _ => i % 160,
+        })
+        .collect();
+
+    bench("resolve_many", || {
+        black_box(reasons.resolve_many(black_box(&values)));
+    });
+    bench("format_reason", || {
+        for val in values.iter() {
+            black_box(reasons.format_reason(black_box(*val), false, Base::Dec));
+        }
+    });
+}
@@ -0,0 +1,28 @@
+//! Former names of the drop reasons the kernel renamed while keeping their
+//! semantics, curated from the history of the kernel's
+//! include/net/dropreason-core.h. The table is partial: reasons missing from
+//! it are not known to have been renamed, rather than guessed.
+
+// (former name, current name), sorted by former name for binary search
+// lookups.
+const ALIASES: &[(&str, &str)] = &[(
+    "SKB_DROP_REASON_PTYPE_ABSENT",
+    "SKB_DROP_REASON_UNHANDLED_PROTO",
+)];
+
+/// Returns the current name of a drop reason given one of its former (full)
+/// names, eg. `SKB_DROP_REASON_PTYPE_ABSENT`, if known.
+pub fn current(name: &str) -> Option<&'static str> {
+    ALIASES
+        .binary_search_by_key(&name, |(former, _)| former)
+        .ok()
+        .map(|i| ALIASES[i].1)
+}
+
+/// Returns the former names of a drop reason given its current (full) name.
+pub fn former(name: &str) -> impl Iterator<Item = &'static str> + '_ {
+    ALIASES
+        .iter()
+        .filter(move |(_, current)| *current == name)
+        .map(|(former, _)| *former)
+}
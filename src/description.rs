@@ -0,0 +1,95 @@
+//! Human readable descriptions of the core drop reasons, curated from the
+//! comments in the kernel's include/net/dropreason-core.h.
+
+// Sorted by name, for binary search lookups.
+const DESCRIPTIONS: &[(&str, &str)] = &[
+    ("SKB_CONSUMED", "packet has been consumed"),
+    ("SKB_DROP_REASON_BPF_CGROUP_EGRESS", "dropped by BPF_PROG_TYPE_CGROUP_SKB eBPF program"),
+    ("SKB_DROP_REASON_CPU_BACKLOG", "failed to enqueue the skb to the per CPU backlog queue (backlog queue full or RPS flow limit)"),
+    ("SKB_DROP_REASON_DEV_HDR", "device driver specific header/metadata is invalid"),
+    ("SKB_DROP_REASON_DEV_READY", "the device is not ready to xmit/recv"),
+    ("SKB_DROP_REASON_DUP_FRAG", "duplicate fragment"),
+    ("SKB_DROP_REASON_FRAG_REASM_TIMEOUT", "fragment reassembly timeout"),
+    ("SKB_DROP_REASON_FRAG_TOO_FAR", "IPv4 fragment too far (see ipfrag_max_dist)"),
+    ("SKB_DROP_REASON_FULL_RING", "ring buffer is full"),
+    ("SKB_DROP_REASON_HDR_TRUNC", "failed to trunc/extract the header from networking data"),
+    ("SKB_DROP_REASON_ICMP_CSUM", "ICMP checksum error"),
+    ("SKB_DROP_REASON_INVALID_PROTO", "the packet doesn't follow the protocol specification"),
+    ("SKB_DROP_REASON_IPV6DISABLED", "IPv6 is disabled on the device"),
+    ("SKB_DROP_REASON_IPV6_BAD_EXTHDR", "bad IPv6 extension header"),
+    ("SKB_DROP_REASON_IPV6_NDISC_BAD_CODE", "invalid NDISC icmp6 code"),
+    ("SKB_DROP_REASON_IPV6_NDISC_BAD_OPTIONS", "invalid NDISC options"),
+    ("SKB_DROP_REASON_IPV6_NDISC_FRAG", "invalid frag (suppress_frag_ndisc)"),
+    ("SKB_DROP_REASON_IPV6_NDISC_HOP_LIMIT", "invalid hop limit"),
+    ("SKB_DROP_REASON_IPV6_NDISC_NS_OTHERHOST", "neighbour solicitation for another host"),
+    ("SKB_DROP_REASON_IP_CSUM", "IP checksum error"),
+    ("SKB_DROP_REASON_IP_INADDRERRORS", "host unreachable (see IPSTATS_MIB_INADDRERRORS)"),
+    ("SKB_DROP_REASON_IP_INHDR", "something wrong with the IP header (see IPSTATS_MIB_INHDRERRORS)"),
+    ("SKB_DROP_REASON_IP_INNOROUTES", "network unreachable (see IPSTATS_MIB_INNOROUTES)"),
+    ("SKB_DROP_REASON_IP_NOPROTO", "no support for IP protocol"),
+    ("SKB_DROP_REASON_IP_OUTNOROUTES", "route lookup failed"),
+    ("SKB_DROP_REASON_IP_RPFILTER", "IP rpfilter validation failed"),
+    ("SKB_DROP_REASON_NEIGH_CREATEFAIL", "failed to create neigh entry"),
+    ("SKB_DROP_REASON_NEIGH_DEAD", "neigh entry is dead"),
+    ("SKB_DROP_REASON_NEIGH_FAILED", "neigh entry in failed state"),
+    ("SKB_DROP_REASON_NEIGH_QUEUEFULL", "arp_queue for neigh entry is full"),
+    ("SKB_DROP_REASON_NETFILTER_DROP", "dropped by netfilter"),
+    ("SKB_DROP_REASON_NOMEM", "error due to OOM"),
+    ("SKB_DROP_REASON_NOT_SPECIFIED", "drop reason is not specified"),
+    ("SKB_DROP_REASON_NO_SOCKET", "socket not found"),
+    ("SKB_DROP_REASON_OTHERHOST", "packet doesn't belong to the current host (interface in promisc mode)"),
+    ("SKB_DROP_REASON_PACKET_SOCK_ERROR", "generic packet socket errors"),
+    ("SKB_DROP_REASON_PKT_TOO_BIG", "packet size is too big (maybe exceeds the MTU)"),
+    ("SKB_DROP_REASON_PKT_TOO_SMALL", "packet size is too small"),
+    ("SKB_DROP_REASON_PROTO_MEM", "proto memory limitation (eg. udp_memory_allocated exceeded)"),
+    ("SKB_DROP_REASON_QDISC_DROP", "dropped by qdisc when packet outputting (failed to enqueue)"),
+    ("SKB_DROP_REASON_QUEUE_PURGE", "bulk free"),
+    ("SKB_DROP_REASON_SKB_CSUM", "sk_buff checksum computation error"),
+    ("SKB_DROP_REASON_SKB_GSO_SEG", "gso segmentation error"),
+    ("SKB_DROP_REASON_SKB_UCOPY_FAULT", "failed to copy data from user space"),
+    ("SKB_DROP_REASON_SOCKET_BACKLOG", "failed to add skb to socket backlog (see LINUX_MIB_TCPBACKLOGDROP)"),
+    ("SKB_DROP_REASON_SOCKET_FILTER", "dropped by socket filter"),
+    ("SKB_DROP_REASON_SOCKET_RCVBUFF", "socket receive buff is full"),
+    ("SKB_DROP_REASON_TAP_FILTER", "dropped by (ebpf) filter directly attached to tun/tap"),
+    ("SKB_DROP_REASON_TAP_TXFILTER", "dropped by tx filter implemented at tun/tap"),
+    ("SKB_DROP_REASON_TCP_ACK_UNSENT_DATA", "TCP ACK for data we haven't sent yet"),
+    ("SKB_DROP_REASON_TCP_CLOSE", "TCP socket in CLOSE state"),
+    ("SKB_DROP_REASON_TCP_CSUM", "TCP checksum error"),
+    ("SKB_DROP_REASON_TCP_FASTOPEN", "dropped by FASTOPEN request socket"),
+    ("SKB_DROP_REASON_TCP_FLAGS", "TCP flags invalid"),
+    ("SKB_DROP_REASON_TCP_INVALID_SEQUENCE", "not acceptable TCP SEQ field"),
+    ("SKB_DROP_REASON_TCP_INVALID_SYN", "incoming TCP packet has unexpected SYN flag"),
+    ("SKB_DROP_REASON_TCP_MD5FAILURE", "TCP MD5 hash is wrong (see LINUX_MIB_TCPMD5FAILURE)"),
+    ("SKB_DROP_REASON_TCP_MD5NOTFOUND", "no TCP MD5 hash and one expected (see LINUX_MIB_TCPMD5NOTFOUND)"),
+    ("SKB_DROP_REASON_TCP_MD5UNEXPECTED", "TCP MD5 hash and none expected (see LINUX_MIB_TCPMD5UNEXPECTED)"),
+    ("SKB_DROP_REASON_TCP_MINTTL", "IPv4 ttl or IPv6 hoplimit below the threshold (IP_MINTTL or IPV6_MINHOPCOUNT)"),
+    ("SKB_DROP_REASON_TCP_OFOMERGE", "the data of skb is already in the ofo queue (see LINUX_MIB_TCPOFOMERGE)"),
+    ("SKB_DROP_REASON_TCP_OFO_DROP", "TCP data already in receive queue"),
+    ("SKB_DROP_REASON_TCP_OFO_QUEUE_PRUNE", "pruned from TCP OFO queue"),
+    ("SKB_DROP_REASON_TCP_OLD_ACK", "TCP ACK is old, but in window"),
+    ("SKB_DROP_REASON_TCP_OLD_DATA", "the TCP data was already received before (spurious retransmission)"),
+    ("SKB_DROP_REASON_TCP_OVERWINDOW", "the TCP data is out of window"),
+    ("SKB_DROP_REASON_TCP_RESET", "invalid TCP RST packet"),
+    ("SKB_DROP_REASON_TCP_RFC7323_PAWS", "TCP PAWS check failed (see LINUX_MIB_PAWSESTABREJECTED)"),
+    ("SKB_DROP_REASON_TCP_TOO_OLD_ACK", "TCP ACK is too old"),
+    ("SKB_DROP_REASON_TCP_ZEROWINDOW", "TCP receive window size is zero (see LINUX_MIB_TCPZEROWINDOWDROP)"),
+    ("SKB_DROP_REASON_TC_CHAIN_NOTFOUND", "tc chain lookup failed"),
+    ("SKB_DROP_REASON_TC_COOKIE_ERROR", "an error occurred while processing a tc ext cookie"),
+    ("SKB_DROP_REASON_TC_EGRESS", "dropped in TC egress hook"),
+    ("SKB_DROP_REASON_TC_INGRESS", "dropped in TC ingress hook"),
+    ("SKB_DROP_REASON_TC_RECLASSIFY_LOOP", "tc exceeded max reclassify loop iterations"),
+    ("SKB_DROP_REASON_UDP_CSUM", "UDP checksum error"),
+    ("SKB_DROP_REASON_UNHANDLED_PROTO", "protocol not implemented or not supported"),
+    ("SKB_DROP_REASON_UNICAST_IN_L2_MULTICAST", "destination address of L2 is multicast, but L3 is unicast"),
+    ("SKB_DROP_REASON_XDP", "dropped by XDP in input path"),
+    ("SKB_DROP_REASON_XFRM_POLICY", "xfrm policy check failed"),
+    ("SKB_NOT_DROPPED_YET", "skb is not dropped yet (used for no-drop case)"),
+];
+
+/// Returns the description of a drop reason given its name, if known.
+pub fn get(name: &str) -> Option<&'static str> {
+    DESCRIPTIONS
+        .binary_search_by(|(n, _)| n.cmp(&name))
+        .ok()
+        .map(|i| DESCRIPTIONS[i].1)
+}
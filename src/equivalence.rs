@@ -0,0 +1,55 @@
+//! Groups of drop reasons reporting the same high-level cause (eg. a checksum
+//! failure, whatever the protocol), for summaries to give a higher-level view
+//! than the individual reasons. The table is partial and only covers core drop
+//! reasons: reasons missing from it are their own group.
+
+// (reason name, group), sorted by reason name for binary search lookups.
+const EQUIVALENCES: &[(&str, &str)] = &[
+    ("SKB_DROP_REASON_BPF_CGROUP_EGRESS", "filtered"),
+    ("SKB_DROP_REASON_CPU_BACKLOG", "queue-full"),
+    ("SKB_DROP_REASON_FULL_RING", "queue-full"),
+    ("SKB_DROP_REASON_HDR_TRUNC", "malformed"),
+    ("SKB_DROP_REASON_ICMP_CSUM", "checksum"),
+    ("SKB_DROP_REASON_IPV6_BAD_EXTHDR", "malformed"),
+    ("SKB_DROP_REASON_IP_CSUM", "checksum"),
+    ("SKB_DROP_REASON_IP_INADDRERRORS", "routing"),
+    ("SKB_DROP_REASON_IP_INHDR", "malformed"),
+    ("SKB_DROP_REASON_IP_INNOROUTES", "routing"),
+    ("SKB_DROP_REASON_IP_OUTNOROUTES", "routing"),
+    ("SKB_DROP_REASON_IP_RPFILTER", "routing"),
+    ("SKB_DROP_REASON_NEIGH_CREATEFAIL", "neighbour"),
+    ("SKB_DROP_REASON_NEIGH_DEAD", "neighbour"),
+    ("SKB_DROP_REASON_NEIGH_FAILED", "neighbour"),
+    ("SKB_DROP_REASON_NEIGH_QUEUEFULL", "neighbour"),
+    ("SKB_DROP_REASON_NETFILTER_DROP", "filtered"),
+    ("SKB_DROP_REASON_NOMEM", "memory"),
+    ("SKB_DROP_REASON_PKT_TOO_SMALL", "malformed"),
+    ("SKB_DROP_REASON_PROTO_MEM", "memory"),
+    ("SKB_DROP_REASON_QDISC_DROP", "queue-full"),
+    ("SKB_DROP_REASON_SKB_CSUM", "checksum"),
+    ("SKB_DROP_REASON_SOCKET_BACKLOG", "queue-full"),
+    ("SKB_DROP_REASON_SOCKET_FILTER", "filtered"),
+    ("SKB_DROP_REASON_SOCKET_RCVBUFF", "queue-full"),
+    ("SKB_DROP_REASON_TCP_AOFAILURE", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_AOKEYNOTFOUND", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_AONOTFOUND", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_AOUNEXPECTED", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_AUTH_HDR", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_CSUM", "checksum"),
+    ("SKB_DROP_REASON_TCP_MD5FAILURE", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_MD5NOTFOUND", "tcp-auth"),
+    ("SKB_DROP_REASON_TCP_MD5UNEXPECTED", "tcp-auth"),
+    ("SKB_DROP_REASON_TC_EGRESS", "filtered"),
+    ("SKB_DROP_REASON_TC_INGRESS", "filtered"),
+    ("SKB_DROP_REASON_UDP_CSUM", "checksum"),
+    ("SKB_DROP_REASON_XDP", "filtered"),
+];
+
+/// Returns the group of a drop reason given its (full) name, eg. `checksum`
+/// for `SKB_DROP_REASON_TCP_CSUM`, if known.
+pub fn group(name: &str) -> Option<&'static str> {
+    EQUIVALENCES
+        .binary_search_by_key(&name, |(reason, _)| reason)
+        .ok()
+        .map(|i| EQUIVALENCES[i].1)
+}
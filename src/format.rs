@@ -0,0 +1,2426 @@
+//! Output formats for drop reasons.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::{Base, DropReasons, Resolved};
+
+/// Minimum kernel version (major, minor) the generated monitoring scripts can
+/// run on: the `kfree_skb` tracepoint reports a drop reason since Linux 5.17.
+pub const SCRIPT_MIN_KERNEL: (u32, u32) = (5, 17);
+
+// Maximum width of a raw drop reason value, in decimal.
+const MAX_VALUE_WIDTH: usize = 10;
+// Width of a raw drop reason value, in hexadecimal (including the 0x prefix).
+const MAX_HEX_VALUE_WIDTH: usize = 10;
+
+/// Formats all the drop reasons, one per line, as `<value> = <name>`. Values
+/// are right-aligned on the largest known value, or on the largest possible
+/// value if `opts.deterministic` is set so the output layout does not depend on
+/// the set of reasons found. Hexadecimal values always have the same width. If
+/// `opts.stability` is set, the stability of each reason is appended when
+/// known. If `opts.by_subsys` is set, reasons are grouped by sub-system, each
+/// group starting with a header naming the sub-system. If `opts.align_names` is
+/// set, what follows the names is aligned on the longest name.
+pub fn raw(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        verbose,
+        deterministic,
+        stability,
+        by_subsys,
+        align_names,
+        base,
+        ..
+    } = *opts;
+    let width = match (base, deterministic) {
+        (Base::Hex, _) => MAX_HEX_VALUE_WIDTH,
+        (Base::Dec, true) => MAX_VALUE_WIDTH,
+        (Base::Dec, false) => {
+            (reasons
+                .by_value()
+                .last()
+                .map(|(val, _)| val)
+                .unwrap_or(0)
+                .checked_ilog10()
+                .unwrap_or(0)
+                + 1) as usize
+        }
+    };
+
+    let name_width = reasons
+        .by_value()
+        .map(|(_, name)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    let mut subsys = None;
+    reasons.by_value().for_each(|(i, _)| {
+        if by_subsys && subsys != Some(i >> 16) {
+            if subsys.is_some() {
+                lines.push(String::new());
+            }
+            lines.push(match reasons.subsystem_for(i) {
+                Some(name) => format!("{name}:"),
+                None => format!("Unknown sub-system {}:", i >> 16),
+            });
+            subsys = Some(i >> 16);
+        }
+
+        let mut reason = reasons.format_reason(i, verbose, base);
+        if let Some(stability) = reasons.stability(i).filter(|_| stability) {
+            reason.push_str(&format!(" [{}]", stability.as_str()));
+        }
+        // Known reasons are formatted starting with their name.
+        if let Some(name) = reasons.get(i).filter(|_| align_names) {
+            let suffix = reason.split_off(name.len());
+            reason = format!("{name:name_width$}{suffix}").trim_end().to_string();
+        }
+
+        lines.push(format!("{:>width$} = {reason}", base.format(i)));
+    });
+
+    lines.join("\n")
+}
+
+// Fits the lines of the raw output in `width` columns, truncating or wrapping
+// what follows the " = " separator (the name column) as set by `fit`. Lines
+// without a separator (eg. sub-system headers) are left untouched.
+fn fit_names(raw: &str, width: usize, fit: NameFit, ascii: bool) -> String {
+    let ellipsis = if ascii { '~' } else { '…' };
+
+    raw.lines()
+        .map(|line| {
+            let (prefix, reason) = match line.split_once(" = ") {
+                Some(split) => split,
+                None => return line.to_string(),
+            };
+            let indent = prefix.chars().count() + 3;
+            let available = width.saturating_sub(indent);
+            let chars: Vec<_> = reason.chars().collect();
+            if chars.len() <= available || available < 2 {
+                return line.to_string();
+            }
+
+            match fit {
+                NameFit::Overflow => line.to_string(),
+                NameFit::Truncate => format!(
+                    "{prefix} = {}{ellipsis}",
+                    chars[..available - 1].iter().collect::<String>()
+                ),
+                NameFit::Wrap => wrap(reason, available)
+                    .iter()
+                    .enumerate()
+                    .map(|(i, chunk)| match i {
+                        0 => format!("{prefix} = {chunk}"),
+                        _ => format!("{:indent$}{chunk}", ""),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Splits a string in chunks of at most `width` characters, breaking after the
+// last underscore or space of a chunk when possible for names to be cut
+// between words.
+fn wrap(s: &str, width: usize) -> Vec<String> {
+    let mut chars: &[char] = &s.chars().collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    while chars.len() > width {
+        let len = match chars[..width].iter().rposition(|c| *c == '_' || *c == ' ') {
+            Some(pos) if pos > 0 => pos + 1,
+            _ => width,
+        };
+        chunks.push(
+            chars[..len]
+                .iter()
+                .collect::<String>()
+                .trim_end()
+                .to_string(),
+        );
+        chars = &chars[len..];
+    }
+    chunks.push(chars.iter().collect());
+    chunks
+}
+
+/// Formats all the drop reasons, one per line, as `<value>\t<name>` without
+/// any padding, for the output to be easily processed by scripts.
+pub fn raw_compact(reasons: &DropReasons, opts: &Opts) -> String {
+    reasons
+        .by_value()
+        .map(|(val, name)| format!("{}\t{name}", opts.base.format(val)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats all the drop reasons as a table of their value, name and sub-system,
+/// with Unicode box-drawing borders (ASCII ones if `opts.ascii` is set), for
+/// terminal display. If `opts.width` is set, names are truncated for the table
+/// to fit in that many columns, or wrapped onto continuation rows if
+/// `opts.name_fit` is [`NameFit::Wrap`].
+pub fn table(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        ascii,
+        width,
+        name_fit: fit,
+        base,
+        ..
+    } = *opts;
+    // Borders are [horizontal, vertical, top, middle and bottom junctions (left,
+    // inner, right)].
+    let (h, v, junctions) = match ascii {
+        true => ('-', '|', [['+'; 3]; 3]),
+        false => (
+            '─',
+            '│',
+            [['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']],
+        ),
+    };
+    let ellipsis = if ascii { '~' } else { '…' };
+
+    let header = ["Value", "Name", "Sub-system"];
+    let rows: Vec<[String; 3]> = reasons
+        .by_value()
+        .map(|(val, name)| {
+            let subsys = match reasons.subsystem_for(val) {
+                Some(subsys) => subsys
+                    .strip_prefix("SKB_DROP_REASON_SUBSYS_")
+                    .unwrap_or(subsys),
+                None => "-",
+            };
+            [base.format(val), name.to_string(), subsys.to_string()]
+        })
+        .collect();
+
+    let mut widths = header.map(|h| h.chars().count());
+    rows.iter().for_each(|row| {
+        widths
+            .iter_mut()
+            .zip(row)
+            .for_each(|(width, cell)| *width = (*width).max(cell.chars().count()))
+    });
+    // Each column is padded with a space on both sides and there are four
+    // vertical borders.
+    if let Some(width) = width {
+        let others = widths[0] + widths[2] + 3 * 2 + 4;
+        widths[1] = widths[1]
+            .min(width.saturating_sub(others))
+            .max(header[1].len());
+    }
+
+    let border = |[left, inner, right]: [char; 3]| {
+        let mut line = left.to_string();
+        widths.iter().enumerate().for_each(|(i, width)| {
+            if i > 0 {
+                line.push(inner);
+            }
+            line.push_str(&h.to_string().repeat(width + 2));
+        });
+        line.push(right);
+        line
+    };
+    let line = |cells: [&str; 3]| {
+        let mut line = v.to_string();
+        cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .for_each(|(i, (cell, width))| {
+                let cell = match cell.chars().count() > width {
+                    true => format!(
+                        "{}{ellipsis}",
+                        cell.chars().take(width - 1).collect::<String>()
+                    ),
+                    false => cell.to_string(),
+                };
+                // Values are right aligned.
+                match i {
+                    0 => write!(line, " {cell:>width$} {v}").unwrap(),
+                    _ => write!(line, " {cell:<width$} {v}").unwrap(),
+                }
+            });
+        line
+    };
+
+    let mut lines = vec![border(junctions[0]), line(header), border(junctions[1])];
+    rows.iter().for_each(|row| match fit {
+        NameFit::Wrap => {
+            let name = wrap(&row[1], widths[1]);
+            lines.push(line([&row[0], &name[0], &row[2]]));
+            name[1..]
+                .iter()
+                .for_each(|chunk| lines.push(line(["", chunk, ""])));
+        }
+        _ => lines.push(line([&row[0], &row[1], &row[2]])),
+    });
+    lines.push(border(junctions[2]));
+    lines.join("\n")
+}
+
+/// Formats an awk program fragment filling a `drop_reason` array (indexed by
+/// raw value) in a `BEGIN` block and defining a `drop_reason_name(val)`
+/// function translating a decimal raw value, eg. a log field, into its name or
+/// `unknown(<val>)`.
+pub fn awk(reasons: &DropReasons) -> String {
+    let reasons_def = reasons
+        .by_value()
+        .fold(String::new(), |mut out, (val, name)| {
+            writeln!(out, "    drop_reason[{val}] = \"{}\"", quote_escape(name)).unwrap();
+            out
+        });
+
+    format!(
+        "# {}
+BEGIN {{
+{reasons_def}}}
+
+function drop_reason_name(val) {{
+    # Normalize the value, eg. 03 to 3.
+    val = val + 0
+    if (val in drop_reason)
+        return drop_reason[val]
+    return \"unknown(\" val \")\"
+}}",
+        generated_by(reasons)
+    )
+}
+
+// Escapes a name embedded in a double quoted string of a generated awk,
+// bpftrace or SystemTap script.
+fn quote_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Formats an extended regular expression matching any of the drop reason
+/// names as a whole word, eg. to search the kernel logs with
+/// `dmesg | grep -E "$(drdump dump -f kmsg-grep)"`. Regular expression
+/// special characters in names are matched literally using bracket expressions
+/// (eg. `[.]`, and `[]]` as `]` must come first), except `^` which is escaped
+/// with a backslash and `}` which is not special on its own. Only POSIX
+/// constructs are used for the expression to work with any grep (eg. not only
+/// GNU's).
+pub fn kmsg_grep(reasons: &DropReasons) -> Result<String> {
+    let names: BTreeSet<_> = reasons
+        .by_value()
+        .map(|(_, name)| name)
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.is_empty() {
+        // An empty alternation would match every line.
+        bail!("No drop reason to build a pattern from");
+    }
+
+    let names = names
+        .iter()
+        .map(|name| {
+            name.chars().fold(String::new(), |mut out, c| {
+                match c {
+                    // A circumflex would negate a bracket expression.
+                    '^' => out.push_str("\\^"),
+                    '\\' | '.' | '$' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' => {
+                        write!(out, "[{c}]").unwrap()
+                    }
+                    c => out.push(c),
+                }
+                out
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("|");
+    // Word boundaries (\b) are a GNU extension, match a non word character
+    // or the start (end) of the line instead.
+    Ok(format!("(^|[^A-Za-z0-9_])({names})($|[^A-Za-z0-9_])"))
+}
+
+/// Formats a PowerShell script defining a `$DropReasons` hashtable mapping raw
+/// values (as integer keys) to reason names, eg. to be dot-sourced and used as
+/// `$DropReasons[2]`.
+pub fn powershell(reasons: &DropReasons) -> String {
+    let reasons_def = reasons
+        .by_value()
+        .fold(String::new(), |mut out, (val, name)| {
+            writeln!(out, "    {val} = '{}'", powershell_escape(name)).unwrap();
+            out
+        });
+
+    format!(
+        "# {}
+$DropReasons = @{{
+{reasons_def}}}",
+        generated_by(reasons)
+    )
+}
+
+/// Formats the drop reasons as `# reason <value> = <name>` comment lines, eg.
+/// to be pasted in Suricata (or other IDS) rule files for rules to reference
+/// the drop reasons.
+pub fn suricata(reasons: &DropReasons) -> String {
+    reasons.by_value().fold(
+        format!("# {}", generated_by(reasons)),
+        |mut out, (val, name)| {
+            write!(out, "\n# reason {val} = {name}").unwrap();
+            out
+        },
+    )
+}
+
+/// Formats a Vim syntax file highlighting the drop reason names, eg. in log
+/// buffers (`:source` it). Names are `dropReason` keywords, linked to the
+/// `Constant` highlight group. Names which are not made of keyword characters
+/// (letters, digits and underscores) are skipped.
+pub fn vim(reasons: &DropReasons) -> String {
+    let names: BTreeSet<_> = reasons
+        .by_value()
+        .map(|(_, name)| name)
+        .filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .collect();
+
+    let mut out = format!("\" {}\n", generated_by(reasons));
+    names
+        .iter()
+        .for_each(|name| writeln!(out, "syntax keyword dropReason {name}").unwrap());
+    out.push_str("highlight default link dropReason Constant");
+    out
+}
+
+/// Formats all the drop reasons as a tree, like the output of the `tree`
+/// command: sub-systems are the top-level nodes, core reasons being under a
+/// `core` one, with their reasons beneath. Branches are drawn using ASCII
+/// characters only if `opts.ascii` is set.
+pub fn tree(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts { ascii, base, .. } = *opts;
+    let (branch, last) = match ascii {
+        true => ("|-- ", "`-- "),
+        false => ("├── ", "└── "),
+    };
+
+    let mut buckets: BTreeMap<u32, Vec<(u32, &str)>> = BTreeMap::new();
+    reasons
+        .by_value()
+        .for_each(|(val, name)| buckets.entry(val >> 16).or_default().push((val, name)));
+
+    let mut lines = Vec::new();
+    buckets.iter().for_each(|(id, members)| {
+        lines.push(match (id, reasons.subsys_name(*id)) {
+            (0, _) => "core".to_string(),
+            (_, Some(name)) => name.to_string(),
+            (_, None) => format!("Unknown sub-system {id}"),
+        });
+        members.iter().enumerate().for_each(|(i, (val, name))| {
+            let prefix = if i + 1 == members.len() { last } else { branch };
+            lines.push(format!("{prefix}{} {name}", base.format(*val)));
+        });
+    });
+
+    lines.join("\n")
+}
+
+/// Formats a single drop reason for a terminal: its name, value, sub-system,
+/// stability and description, when known. Returns `None` if the reason is
+/// not known.
+pub fn explain(reasons: &DropReasons, val: u32) -> Option<String> {
+    let reason = reasons.reason(val)?;
+
+    // Use the labels, if renamed, as the output is for humans.
+    let mut out = reasons.get(val).unwrap_or(&reason.name).to_string();
+    write!(out, "\n  Value:      {} ({})", val, Base::Hex.format(val)).unwrap();
+    if let Some(subsys) = reasons.subsystem_for(val) {
+        write!(out, "\n  Sub-system: {subsys}").unwrap();
+        write!(out, "\n  Index:      {}", reason.index).unwrap();
+    }
+    if let Some(stability) = reason.stability {
+        write!(out, "\n  Stability:  {}", stability.as_str()).unwrap();
+    }
+    if let Some(consume) = reason.is_consume {
+        let kind = if consume { "consume" } else { "drop" };
+        write!(out, "\n  Kind:       {kind}").unwrap();
+    }
+    match &reason.description {
+        Some(desc) => write!(out, "\n\n{}", comment(" ", desc, Some(80))).unwrap(),
+        None => out.push_str("\n\n  No description available."),
+    }
+
+    Some(out)
+}
+
+/// Formats all the drop reasons as a JSON array of objects.
+pub fn json(reasons: &DropReasons) -> Result<String> {
+    Ok(serde_json::to_string_pretty(
+        &reasons.iter().collect::<Vec<_>>(),
+    )?)
+}
+
+/// Formats the JSON Schema of the [`json`] format output, an array of
+/// [`DropReason`](crate::DropReason) objects, generated from the serialized
+/// type for it to stay in sync.
+#[cfg(feature = "schema")]
+pub fn json_schema() -> Result<String> {
+    Ok(serde_json::to_string_pretty(&schemars::schema_for!(
+        Vec<crate::DropReason>
+    ))?)
+}
+
+/// Serializes all the drop reasons as MessagePack, using the same schema as
+/// the [`json`] format.
+#[cfg(feature = "msgpack")]
+pub fn msgpack(reasons: &DropReasons) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec_named(
+        &reasons.iter().collect::<Vec<_>>(),
+    )?)
+}
+
+/// Serializes all the drop reasons as CBOR, using the same schema as the
+/// [`json`] format.
+#[cfg(feature = "cbor")]
+pub fn cbor(reasons: &DropReasons) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ciborium::into_writer(&reasons.iter().collect::<Vec<_>>(), &mut out)?;
+    Ok(out)
+}
+
+/// Formats all the drop reasons as RON, using the same schema as the [`json`]
+/// format.
+#[cfg(feature = "ron")]
+pub fn ron(reasons: &DropReasons) -> Result<String> {
+    Ok(ron::ser::to_string_pretty(
+        &reasons.iter().collect::<Vec<_>>(),
+        ron::ser::PrettyConfig::default(),
+    )?)
+}
+
+/// Formats all the drop reasons as a flat JSON object mapping raw values
+/// (as strings) to reason names. Keys are ordered by ascending raw value.
+pub fn json_flat(reasons: &DropReasons) -> Result<String> {
+    // Built by hand as serializing a map would sort the keys as strings.
+    let entries = reasons
+        .by_value()
+        .map(|(val, name)| Ok(format!("  \"{val}\": {}", serde_json::to_string(name)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(match entries.is_empty() {
+        true => "{}".to_string(),
+        false => format!("{{\n{}\n}}", entries.join(",\n")),
+    })
+}
+
+/// Formats all the drop reasons as a flat JSON object mapping reason names to
+/// raw values. Keys are ordered by name. The rare names shared by several
+/// reasons map to an array of their values, by ascending value.
+pub fn json_by_name(reasons: &DropReasons) -> Result<String> {
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    enum Values {
+        One(u32),
+        Many(Vec<u32>),
+    }
+
+    let mut names: BTreeMap<&str, Vec<u32>> = BTreeMap::new();
+    reasons
+        .by_value()
+        .for_each(|(val, name)| names.entry(name).or_default().push(val));
+
+    let names: BTreeMap<_, _> = names
+        .into_iter()
+        .map(|(name, mut values)| match values.len() {
+            1 => (name, Values::One(values.remove(0))),
+            _ => (name, Values::Many(values)),
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&names)?)
+}
+
+/// Formats the drop reasons as JSON facts (eg. for Ansible custom facts): an
+/// object with a single `drop_reasons` key holding the number of reasons and
+/// objects mapping reason and sub-system names to their raw value and id. The
+/// rare names shared by several reasons map to their lowest value.
+pub fn facts(reasons: &DropReasons) -> Result<String> {
+    #[derive(Serialize)]
+    struct Facts<'a> {
+        drop_reasons: DropReasonsFacts<'a>,
+    }
+
+    #[derive(Serialize)]
+    struct DropReasonsFacts<'a> {
+        count: usize,
+        reasons: BTreeMap<&'a str, u32>,
+        subsystems: BTreeMap<&'a str, u32>,
+    }
+
+    let mut names = BTreeMap::new();
+    reasons.by_value().for_each(|(val, name)| {
+        names.entry(name).or_insert(val);
+    });
+
+    Ok(serde_json::to_string_pretty(&Facts {
+        drop_reasons: DropReasonsFacts {
+            count: reasons.len(),
+            reasons: names,
+            subsystems: reasons.subsystems().map(|(id, name)| (name, id)).collect(),
+        },
+    })?)
+}
+
+/// Formats a flat summary of the drop reasons as a single JSON line, eg. to be
+/// exported as CI step outputs. Keys are stable:
+/// - `drop_reason_count`: the number of drop reasons.
+/// - `core_reason_count`: the number of core drop reasons.
+/// - `subsystem_count`: the number of sub-systems defining at least one reason.
+/// - `has_ovs_reasons`: whether the Open vSwitch sub-system defines reasons.
+/// - `unknown_subsystems`: the number of sub-systems defining reasons but not
+///   found in the BTF.
+pub fn env_json(reasons: &DropReasons) -> Result<String> {
+    #[derive(Serialize)]
+    struct Env {
+        drop_reason_count: usize,
+        core_reason_count: usize,
+        subsystem_count: usize,
+        has_ovs_reasons: bool,
+        unknown_subsystems: usize,
+    }
+
+    let count = reasons.count_by_subsys();
+    let ovs = reasons.subsys_id("OPENVSWITCH");
+    Ok(serde_json::to_string(&Env {
+        drop_reason_count: reasons.len(),
+        core_reason_count: count.get(&0).copied().unwrap_or(0),
+        subsystem_count: count.keys().filter(|id| **id != 0).count(),
+        has_ovs_reasons: ovs.is_some_and(|id| count.contains_key(&id)),
+        unknown_subsystems: count
+            .keys()
+            .filter(|id| **id != 0 && reasons.subsys_name(**id).is_none())
+            .count(),
+    })?)
+}
+
+/// Formats resolved values as JSON: a single object if only one value was
+/// resolved, an array of objects otherwise.
+pub fn json_resolved(resolved: &[Resolved<'_>]) -> Result<String> {
+    Ok(match resolved {
+        [resolved] => serde_json::to_string_pretty(resolved)?,
+        _ => serde_json::to_string_pretty(resolved)?,
+    })
+}
+
+/// Formats all the definitions of raw values (see
+/// [`DropReasons::definitions`]) as a JSON array of objects holding the value
+/// and its `(enum, name)` definitions, empty if the value is unknown.
+pub fn json_definitions(reasons: &DropReasons, values: &[u32]) -> Result<String> {
+    #[derive(Serialize)]
+    struct Definitions<'a> {
+        value: u32,
+        definitions: Vec<Definition<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct Definition<'a> {
+        r#enum: &'a str,
+        name: &'a str,
+    }
+
+    Ok(serde_json::to_string_pretty(
+        &values
+            .iter()
+            .map(|val| Definitions {
+                value: *val,
+                definitions: reasons
+                    .definitions(*val)
+                    .into_iter()
+                    .map(|(r#enum, name)| Definition { r#enum, name })
+                    .collect(),
+            })
+            .collect::<Vec<_>>(),
+    )?)
+}
+
+/// Formats all the drop reasons as Perfetto interned data (in its JSON
+/// representation), for traces to label reasons using interned strings.
+/// Interning ids must be non-zero, so a reason iid is its raw value plus one.
+pub fn perfetto(reasons: &DropReasons) -> Result<String> {
+    #[derive(Serialize)]
+    struct InternedString<'a> {
+        iid: u64,
+        str: &'a str,
+    }
+
+    #[derive(Serialize)]
+    struct InternedData<'a> {
+        debug_annotation_string_values: Vec<InternedString<'a>>,
+    }
+
+    Ok(serde_json::to_string_pretty(&InternedData {
+        debug_annotation_string_values: reasons
+            .by_value()
+            .map(|(val, name)| InternedString {
+                iid: val as u64 + 1,
+                str: name,
+            })
+            .collect(),
+    })?)
+}
+
+/// Formats all the drop reasons as a Java properties file, mapping raw values
+/// to reason names.
+pub fn properties(reasons: &DropReasons) -> String {
+    reasons
+        .by_value()
+        .map(|(val, name)| {
+            format!(
+                "{}={}",
+                properties_escape(&val.to_string(), true),
+                properties_escape(name, false)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats all the drop reasons as a Terraform (HCL) `locals` block defining a
+/// `drop_reasons` map from raw values (as strings) to reason names, eg. to be
+/// referenced as `local.drop_reasons["2"]`.
+pub fn hcl(reasons: &DropReasons) -> String {
+    let reasons_def = reasons
+        .by_value()
+        .fold(String::new(), |mut out, (val, name)| {
+            writeln!(out, "    \"{val}\" = \"{}\"", hcl_escape(name)).unwrap();
+            out
+        });
+
+    format!(
+        "# {}
+locals {{
+  drop_reasons = {{
+{reasons_def}  }}
+}}",
+        generated_by(reasons)
+    )
+}
+
+/// Formats all the drop reasons as CUE definitions: `#DropReasons` maps names
+/// to raw values (a disjunction for names used by more than one value),
+/// `#DropReasonName` and `#DropReasonValue` constrain a field to the known
+/// names or values and `#WithDropReason` is an open struct whose `reason`
+/// field is either of those, eg. to validate monitoring configurations.
+pub fn cue(reasons: &DropReasons) -> Result<String> {
+    // Names and their values, by ascending first value.
+    let mut names: Vec<(&str, Vec<u32>)> = Vec::new();
+    for (val, name) in reasons.by_value() {
+        match names.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, values)) => values.push(val),
+            None => names.push((name, vec![val])),
+        }
+    }
+
+    let disjunction = |values: Vec<String>| match values.is_empty() {
+        // Bottom, ie. no value is allowed.
+        true => "_|_".to_string(),
+        false => values.join(" |\n\t"),
+    };
+
+    let mut out = "#DropReasons: {\n".to_string();
+    for (name, values) in names.iter() {
+        let values = values.iter().map(u32::to_string).collect::<Vec<_>>();
+        writeln!(out, "\t{}: {}", cue_label(name)?, values.join(" | ")).unwrap();
+    }
+    out.push_str("}\n\n");
+
+    let quoted = names
+        .iter()
+        .map(|(name, _)| Ok(serde_json::to_string(name)?))
+        .collect::<Result<Vec<_>>>()?;
+    writeln!(out, "#DropReasonName: {}\n", disjunction(quoted)).unwrap();
+    let values = reasons.by_value().map(|(val, _)| val.to_string()).collect();
+    writeln!(out, "#DropReasonValue: {}\n", disjunction(values)).unwrap();
+    out.push_str(
+        "#WithDropReason: {
+\treason: #DropReasonName | #DropReasonValue
+\t...
+}",
+    );
+
+    Ok(out)
+}
+
+// Formats a CUE field label, quoting it if it isn't a valid identifier. Labels
+// starting with `_` or `#` would define hidden fields or definitions, and
+// keywords can't be used as identifiers.
+fn cue_label(name: &str) -> Result<String> {
+    const KEYWORDS: &[&str] = &[
+        "package", "import", "for", "in", "if", "let", "true", "false", "null",
+    ];
+
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.contains(&name);
+
+    Ok(match identifier {
+        true => name.to_string(),
+        // CUE strings are a superset of JSON ones.
+        false => serde_json::to_string(name)?,
+    })
+}
+
+/// Formats all the drop reasons as a YAML document grouping them by
+/// sub-system, each sub-system being a mapping holding its id and its
+/// reasons (mapping raw values to names) and anchored by its lowercase short
+/// name (eg. `&mac80211_unusable`). Core reasons are under a `core` key.
+pub fn yaml_subsys(reasons: &DropReasons) -> Result<String> {
+    let mut out = String::new();
+    let mut subsys = None;
+
+    for (val, name) in reasons.by_value() {
+        let id = val >> 16;
+        if subsys != Some(id) {
+            let key = match (id, reasons.subsystem_for(val)) {
+                (0, _) => "core".to_string(),
+                (_, Some(name)) => name.to_string(),
+                (_, None) => format!("unknown_{id}"),
+            };
+            let anchor = key
+                .strip_prefix("SKB_DROP_REASON_SUBSYS_")
+                .unwrap_or(&key)
+                .to_lowercase();
+            writeln!(out, "{key}: &{anchor}\n  id: {id}\n  reasons:").unwrap();
+            subsys = Some(id);
+        }
+        // JSON strings are valid YAML scalars.
+        writeln!(out, "    {val}: {}", serde_json::to_string(name)?).unwrap();
+    }
+
+    Ok(match out.is_empty() {
+        true => "{}".to_string(),
+        false => out.trim_end().to_string(),
+    })
+}
+
+/// Formats all the drop reasons as InfluxDB line protocol points, one per line,
+/// in a `drop_reason` measurement. The value, name and sub-system (if known)
+/// are tags, the value being also reported as the `code` field as points
+/// require at least one field.
+pub fn influx(reasons: &DropReasons) -> String {
+    reasons
+        .by_value()
+        .map(|(val, name)| {
+            // Tags are sorted by key, as recommended for performance.
+            let mut point = format!("drop_reason,name={}", influx_escape(name));
+            if let Some(subsys) = reasons.subsystem_for(val) {
+                write!(point, ",subsystem={}", influx_escape(subsys)).unwrap();
+            }
+            write!(point, ",value={val} code={val}i").unwrap();
+            point
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats all the drop reasons as an OpenMetrics text exposition of a
+/// `drop_reason_info` gauge, with one sample (always 1) per reason labelled by
+/// its value, name and sub-system (`core` for the core reasons).
+pub fn openmetrics(reasons: &DropReasons) -> String {
+    let mut out = String::from(
+        "# HELP drop_reason_info Drop reasons known by the kernel.
+# TYPE drop_reason_info gauge
+",
+    );
+    reasons.by_value().for_each(|(val, name)| {
+        let subsys = match (val >> 16, reasons.subsystem_for(val)) {
+            (0, _) => "core".to_string(),
+            (_, Some(name)) => name.to_string(),
+            (id, None) => format!("unknown_{id}"),
+        };
+        writeln!(
+            out,
+            "drop_reason_info{{value=\"{val}\",name=\"{}\",subsystem=\"{}\"}} 1",
+            openmetrics_escape(name),
+            openmetrics_escape(&subsys)
+        )
+        .unwrap();
+    });
+    out.push_str("# EOF");
+    out
+}
+
+/// Formats all the drop reasons as ClickHouse SQL statements: the creation of
+/// a `drop_reasons` table (if it does not exist), holding the value, name,
+/// sub-system id and sub-system name (NULL if not known) of each reason,
+/// followed by the insertion of all the reasons. The table uses the
+/// `ReplacingMergeTree` engine, for seeding it again to not keep duplicates.
+pub fn clickhouse(reasons: &DropReasons) -> String {
+    let mut out = format!(
+        "-- {}
+CREATE TABLE IF NOT EXISTS drop_reasons
+(
+    value UInt32,
+    name String,
+    subsys_id UInt16,
+    subsystem Nullable(String)
+)
+ENGINE = ReplacingMergeTree
+ORDER BY value;",
+        generated_by(reasons)
+    );
+
+    let rows = reasons
+        .by_value()
+        .map(|(val, name)| {
+            let subsys = match reasons.subsystem_for(val) {
+                Some(subsys) => clickhouse_quote(subsys),
+                None => "NULL".to_string(),
+            };
+            format!(
+                "({val}, {}, {}, {subsys})",
+                clickhouse_quote(name),
+                val >> 16
+            )
+        })
+        .collect::<Vec<_>>();
+    // An insertion requires at least one row.
+    if !rows.is_empty() {
+        write!(
+            out,
+            "\n\nINSERT INTO drop_reasons VALUES\n{};",
+            rows.join(",\n")
+        )
+        .unwrap();
+    }
+    out
+}
+
+// Escapes a string to be used in a PowerShell single quoted string, where
+// single quotes (including the typographic ones PowerShell also accepts) are
+// escaped by doubling them.
+fn powershell_escape(s: &str) -> String {
+    s.chars().fold(String::new(), |mut out, c| {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            out.push(c);
+        }
+        out.push(c);
+        out
+    })
+}
+
+// Quotes a ClickHouse string literal.
+fn clickhouse_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// Escapes an OpenMetrics label value.
+fn openmetrics_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Escapes a line protocol tag key or value.
+fn influx_escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            ',' | '=' | ' ' | '\\' => write!(out, "\\{c}").unwrap(),
+            // Line breaks are not allowed, even escaped.
+            '\n' | '\r' => (),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Formats `perf probe` and `perf record` commands monitoring drops through a
+/// probe on the `kfree_skb_reason` kernel function, capturing the drop reason
+/// from its second argument register (x86_64 calling convention), followed by
+/// a comment table of the drop reasons to translate the recorded values.
+pub fn perf_probe(reasons: &DropReasons) -> String {
+    let mut out = format!(
+        "# {}
+# Probes kfree_skb_reason, capturing the reason from its second argument (in
+# %si on x86_64), records the drops system-wide for 10 seconds and prints them.
+# On kernels where drops go through sk_skb_reason_drop instead, probe it with
+# the reason being its third argument (reason=%dx:u32).
+perf probe --add 'kfree_skb_reason reason=%si:u32'
+perf record -e probe:kfree_skb_reason -a -- sleep 10
+perf script
+perf probe --del probe:kfree_skb_reason
+#
+# Drop reasons, to translate the recorded reason values:
+",
+        generated_by(reasons)
+    );
+    reasons
+        .by_value()
+        .for_each(|(val, name)| writeln!(out, "# {val:>6} = {name}").unwrap());
+    out.pop();
+    out
+}
+
+/// Formats all the drop reasons as journalctl commands, one per line, matching
+/// the log entries reporting them either by name or as `reason=<VALUE>`, eg.
+/// `journalctl --grep='\b(SKB_DROP_REASON_NO_SOCKET|reason=2)\b'`.
+pub fn journald(reasons: &DropReasons) -> String {
+    reasons
+        .by_value()
+        .map(|(val, name)| {
+            format!(
+                "journalctl --grep='\\b({}|reason={val})\\b'",
+                journald_escape(name)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Escapes a name to match it literally in a single quoted journalctl pattern
+// (a PCRE2 regular expression).
+fn journald_escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' => {
+                write!(out, "\\{c}").unwrap()
+            }
+            // Single quotes can't be escaped in single quoted shell strings.
+            '\'' => out.push_str("'\\''"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Formats all the drop reasons as a C enum, mirroring the kernel's
+/// `enum skb_drop_reason` including the sub-system reasons. Sub-system reasons
+/// are annotated with a trailing comment naming their sub-system.
+pub fn c_enum(reasons: &DropReasons) -> String {
+    let mut out = String::from("enum skb_drop_reason {\n");
+
+    reasons.by_value().for_each(|(val, name)| {
+        write!(out, "\t{name} = {val},").unwrap();
+        if val >> 16 != 0 {
+            match reasons.subsystem_for(val) {
+                Some(subsys) => write!(out, " /* {subsys} */").unwrap(),
+                None => write!(out, " /* Unknown sub-system {} */", val >> 16).unwrap(),
+            }
+        }
+        out.push('\n');
+    });
+
+    out.push_str("};");
+    out
+}
+
+/// Formats all the drop reasons as a reStructuredText simple table of values,
+/// names and sub-systems.
+pub fn rst(reasons: &DropReasons) -> String {
+    let rows: Vec<[String; 3]> = std::iter::once([
+        "Value".to_string(),
+        "Name".to_string(),
+        "Sub-system".to_string(),
+    ])
+    .chain(reasons.by_value().map(|(val, name)| {
+        [
+            val.to_string(),
+            name.to_string(),
+            reasons.subsystem_for(val).unwrap_or_default().to_string(),
+        ]
+    }))
+    .collect();
+
+    // Columns must be at least as wide as their longest cell.
+    let widths: Vec<_> = (0..3)
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let border = widths
+        .iter()
+        .map(|w| "=".repeat(*w))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let line = |row: &[String; 3]| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![border.clone(), line(&rows[0]), border.clone()];
+    lines.extend(rows.iter().skip(1).map(line));
+    lines.push(border);
+    lines.join("\n")
+}
+
+/// Formats all the drop reasons as a LaTeX `tabular` of values, names and
+/// sub-systems.
+pub fn latex(reasons: &DropReasons) -> String {
+    let mut out =
+        "\\begin{tabular}{rll}\n\\hline\nValue & Name & Sub-system \\\\\n\\hline\n".to_string();
+    reasons.by_value().for_each(|(val, name)| {
+        writeln!(
+            out,
+            "{val} & {} & {} \\\\",
+            latex_escape(name),
+            latex_escape(reasons.subsystem_for(val).unwrap_or_default())
+        )
+        .unwrap()
+    });
+    out.push_str("\\hline\n\\end{tabular}");
+    out
+}
+
+// Escapes the LaTeX special characters of a string used in text mode.
+fn latex_escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => write!(out, "\\{c}").unwrap(),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Formats all the drop reasons as an XML document, one `<reason/>` element
+/// per reason.
+pub fn xml(reasons: &DropReasons) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<drop_reasons>\n");
+
+    reasons.by_value().for_each(|(val, name)| {
+        write!(
+            out,
+            "  <reason value=\"{val}\" name=\"{}\"",
+            xml_escape(name)
+        )
+        .unwrap();
+        if let Some(subsys) = reasons.subsystem_for(val) {
+            write!(out, " subsystem=\"{}\"", xml_escape(subsys)).unwrap();
+        }
+        out.push_str("/>\n");
+    });
+
+    out.push_str("</drop_reasons>");
+    out
+}
+
+// Escapes a string to be used as an XML attribute value. Characters not
+// allowed in XML documents are dropped.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::new();
+
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => write!(out, "&#x{:x};", c as u32).unwrap(),
+            c if c.is_control() => (),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+// Escapes a string to be used in an HCL quoted template: besides the usual
+// escape sequences, template sequences (${ and %{) are escaped by doubling
+// their leading character.
+fn hcl_escape(s: &str) -> String {
+    let mut out = String::new();
+
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '$' | '%' if chars.peek() == Some(&'{') => {
+                out.push(c);
+                out.push(c);
+            }
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// Escapes a string following the java.util.Properties format. Separators and
+// whitespaces are escaped in keys, and leading whitespaces in values.
+fn properties_escape(s: &str, key: bool) -> String {
+    let mut out = String::new();
+
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x0c' => out.push_str("\\f"),
+            '=' | ':' | '#' | '!' if key => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' ' if key || i == 0 => out.push_str("\\ "),
+            c if !c.is_ascii() || c.is_ascii_control() => {
+                for u in c.encode_utf16(&mut [0; 2]) {
+                    write!(out, "\\u{u:04x}").unwrap();
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Formats the number of drop reasons defined by each sub-system, one per
+/// line. Sub-systems not found in the BTF are reported by id. Sub-systems
+/// defining no reason are omitted if `opts.hide_empty` is set.
+pub fn subsys_count(reasons: &DropReasons, opts: &Opts) -> String {
+    let count = subsys_count_all(reasons, opts.hide_empty);
+    let names: Vec<_> = count
+        .keys()
+        .map(|id| match reasons.subsys_name(*id) {
+            Some(name) => name.to_string(),
+            None => format!("Unknown sub-system {id}"),
+        })
+        .collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+
+    names
+        .iter()
+        .zip(count.values())
+        .map(|(name, count)| format!("{name:width$} {count}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Returns the number of drop reasons defined by each sub-system, including the
+// sub-systems defining none unless `hide_empty` is set.
+fn subsys_count_all(reasons: &DropReasons, hide_empty: bool) -> BTreeMap<u32, usize> {
+    let mut count = reasons.count_by_subsys();
+    if !hide_empty {
+        reasons.empty_subsys().into_iter().for_each(|id| {
+            count.insert(id, 0);
+        });
+    }
+    count
+}
+
+/// Formats the number of drop reasons defined by each sub-system as a JSON
+/// object, eg. for dashboards: the `total` number of reasons, the number of
+/// `core` ones, the `subsystems` (as `id`, `name` and `count`, by descending
+/// count, omitting the ones defining no reason if `opts.hide_empty` is set) and
+/// the number of `unknown_subsystems` among them, ie. not found in the BTF
+/// (their `name` being `null`).
+pub fn subsys_count_json(reasons: &DropReasons, opts: &Opts) -> Result<String> {
+    #[derive(Serialize)]
+    struct Subsys<'a> {
+        id: u32,
+        name: Option<&'a str>,
+        count: usize,
+    }
+    #[derive(Serialize)]
+    struct Summary<'a> {
+        total: usize,
+        core: usize,
+        subsystems: Vec<Subsys<'a>>,
+        unknown_subsystems: usize,
+    }
+
+    let count = subsys_count_all(reasons, opts.hide_empty);
+    let mut subsystems: Vec<_> = count
+        .iter()
+        .map(|(id, count)| Subsys {
+            id: *id,
+            name: reasons.subsys_name(*id),
+            count: *count,
+        })
+        .collect();
+    subsystems.sort_by_key(|subsys| std::cmp::Reverse(subsys.count));
+
+    Ok(serde_json::to_string_pretty(&Summary {
+        total: reasons.len(),
+        core: count.get(&0).copied().unwrap_or(0),
+        unknown_subsystems: subsystems.iter().filter(|s| s.name.is_none()).count(),
+        subsystems,
+    })?)
+}
+
+/// Formats the number of drop reasons in each category (see
+/// [`DropReasons::categories`]), or in each group of equivalent reasons if
+/// `opts.group_equivalent` is set (see [`DropReasons::equivalence_groups`]),
+/// one per line, by descending count. If `opts.verbose` is set, the reasons of
+/// each category are listed too.
+pub fn categories(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        verbose,
+        group_equivalent: equivalent,
+        ..
+    } = *opts;
+    let categories = sorted_categories(reasons, equivalent);
+    let width = categories
+        .iter()
+        .map(|(category, _)| category.len())
+        .max()
+        .unwrap_or(0);
+
+    categories
+        .iter()
+        .map(|(category, values)| {
+            let mut out = format!("{category:width$} {}", values.len());
+            if verbose {
+                values.iter().for_each(|val| {
+                    write!(out, "\n  {}", reasons.format_reason(*val, false, Base::Dec)).unwrap()
+                });
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats the drop reason categories (see [`DropReasons::categories`]), or the
+/// groups of equivalent reasons if `opts.group_equivalent` is set, as a JSON
+/// array of objects, by descending count.
+pub fn categories_json(reasons: &DropReasons, opts: &Opts) -> Result<String> {
+    #[derive(Serialize)]
+    struct Category<'a> {
+        category: &'a str,
+        count: usize,
+        reasons: Vec<&'a str>,
+    }
+
+    Ok(serde_json::to_string_pretty(
+        &sorted_categories(reasons, opts.group_equivalent)
+            .into_iter()
+            .map(|(category, values)| Category {
+                category,
+                count: values.len(),
+                // Unwrap as values come from the drop reasons.
+                reasons: values
+                    .iter()
+                    .map(|val| reasons.get(*val).unwrap())
+                    .collect(),
+            })
+            .collect::<Vec<_>>(),
+    )?)
+}
+
+// Returns the drop reason categories (or groups of equivalent reasons) by
+// descending count, then by name.
+fn sorted_categories(reasons: &DropReasons, equivalent: bool) -> Vec<(&str, Vec<u32>)> {
+    let categories = match equivalent {
+        true => reasons.equivalence_groups(),
+        false => reasons.categories(),
+    };
+    let mut categories: Vec<_> = categories.into_iter().collect();
+    // The input is sorted by name and the sort is stable.
+    categories.sort_by_key(|(_, values)| std::cmp::Reverse(values.len()));
+    categories
+}
+
+/// Formats a matrix of the drop reason names (rows, sorted by name) found in
+/// several named sets of drop reasons, eg. one per kernel (columns). Reasons
+/// are marked `x` when present and `-` when absent. The matrix is formatted as
+/// CSV, or as a markdown table if `markdown` is set.
+pub fn matrix(kernels: &[(String, DropReasons)], markdown: bool) -> String {
+    let names: BTreeSet<_> = kernels
+        .iter()
+        .flat_map(|(_, reasons)| reasons.by_value().map(|(_, name)| name))
+        .collect();
+
+    let row = |cells: Vec<String>| match markdown {
+        true => format!(
+            "| {} |",
+            cells
+                .iter()
+                .map(|c| c.replace('|', "\\|"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        ),
+        false => cells
+            .iter()
+            .map(|c| csv_escape(c))
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+
+    let mut lines = vec![row(std::iter::once("reason".to_string())
+        .chain(kernels.iter().map(|(kernel, _)| kernel.clone()))
+        .collect())];
+    if markdown {
+        lines.push(format!("|{}", "---|".repeat(kernels.len() + 1)));
+    }
+
+    names.iter().for_each(|name| {
+        lines.push(row(std::iter::once(name.to_string())
+            .chain(kernels.iter().map(|(_, reasons)| {
+                match reasons.value_of(name) {
+                    Some(_) => "x",
+                    None => "-",
+                }
+                .to_string()
+            }))
+            .collect()))
+    });
+
+    lines.join("\n")
+}
+
+/// Quotes a CSV field if needed, following RFC 4180.
+pub fn csv_escape(s: &str) -> String {
+    match s.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+/// Constructs a bpftrace script to monitor drop reasons. If `opts.min_count` is
+/// set, only the drops seen at least that many times in an interval are
+/// reported. The script attaches to the skb tracepoints of `opts.tracepoints`,
+/// or to `kfree_skb` if none is given. When attached to more than one
+/// tracepoint, drops are also broken down by tracepoint. Only `kfree_skb`
+/// reports a drop reason: drops seen on the other tracepoints are counted
+/// without one. Comments are wrapped at `opts.wrap_width` columns, if set. If
+/// `opts.duration` is set, the script exits on its own after that many seconds.
+/// If `opts.annotate` is set, the script embeds comments explaining how to read
+/// its output.
+pub fn bpftrace(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        ref tracepoints,
+        wrap_width,
+        duration,
+        annotate,
+        ..
+    } = *opts;
+    bpftrace_script(
+        reasons,
+        min_count,
+        tracepoints,
+        wrap_width,
+        duration,
+        annotate,
+        Probe::Map,
+    )
+}
+
+/// Constructs a bpftrace script to monitor drop reasons, as [`bpftrace`], but
+/// resolving the reason names with an `if`/`else if` chain in the probe
+/// instead of a `@drop_reasons` map. This avoids map lookups on every drop,
+/// at the cost of a probe growing with the number of reasons: it is best
+/// suited to small sets of reasons (eg. once filtered).
+pub fn bpftrace_switch(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        ref tracepoints,
+        wrap_width,
+        duration,
+        annotate,
+        ..
+    } = *opts;
+    bpftrace_script(
+        reasons,
+        min_count,
+        tracepoints,
+        wrap_width,
+        duration,
+        annotate,
+        Probe::Switch,
+    )
+}
+
+/// Constructs a bpftrace script to monitor drop reasons, as [`bpftrace`], but
+/// attaching a kprobe to the `kfree_skb_reason` kernel function (reading the
+/// reason from its second argument) instead of the `kfree_skb` tracepoint,
+/// for kernels whose tracepoint lacks the reason. Whether the function is
+/// available depends on the kernel (eg. it can be inlined or renamed). Drops
+/// are reported by kernel stack (the probed function and its caller) instead
+/// of by location.
+pub fn bpftrace_kprobe(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        wrap_width,
+        duration,
+        annotate,
+        ..
+    } = *opts;
+    bpftrace_script(
+        reasons,
+        min_count,
+        &[],
+        wrap_width,
+        duration,
+        annotate,
+        Probe::Kprobe,
+    )
+}
+
+// How a bpftrace script probes and resolves the drop reasons.
+#[derive(PartialEq)]
+enum Probe {
+    // Tracepoints, resolving the names using a map.
+    Map,
+    // Tracepoints, resolving the names in the probe.
+    Switch,
+    // kfree_skb_reason, resolving the names using a map.
+    Kprobe,
+}
+
+// Constructs a bpftrace script to monitor drop reasons (see bpftrace), probing
+// and resolving the reasons as described by `probe`.
+fn bpftrace_script(
+    reasons: &DropReasons,
+    min_count: Option<u64>,
+    tracepoints: &[String],
+    wrap_width: Option<usize>,
+    duration: Option<u64>,
+    annotate: bool,
+    probe: Probe,
+) -> String {
+    let switch = probe == Probe::Switch;
+    let kprobe = probe == Probe::Kprobe;
+    let (reason, location) = match kprobe {
+        false => ("args->reason", "ksym(args->location)"),
+        true => ("arg1", "kstack(2)"),
+    };
+    let consume = |val| match reasons.is_consume(val) {
+        Some(true) => " // consume, not a drop",
+        _ => "",
+    };
+    let (reasons_def, lookup, cleanup) = match switch {
+        false => (
+            reasons
+                .by_value()
+                .fold(String::new(), |mut out, (val, name)| {
+                    writeln!(
+                        out,
+                        "    @drop_reasons[{val}] = \"{}\";{}",
+                        quote_escape(name),
+                        consume(val)
+                    )
+                    .unwrap();
+                    out
+                })
+                + "\n",
+            format!("@drop_reasons[{reason}]"),
+            "\n  clear(@drop_reasons);",
+        ),
+        true => (String::new(), "$reason".to_string(), ""),
+    };
+    let resolve = match switch {
+        false => String::new(),
+        true => {
+            reasons.by_value().enumerate().fold(
+                "    $reason = \"UNKNOWN\";\n    ".to_string(),
+                |mut out, (i, (val, name))| {
+                    if i > 0 {
+                        out.push_str(" else ");
+                    }
+                    write!(
+                        out,
+                        "if ({reason} == {val}) {{
+        $reason = \"{}\";{}
+    }}",
+                        quote_escape(name),
+                        consume(val)
+                    )
+                    .unwrap();
+                    out
+                },
+            ) + "\n"
+        }
+    };
+
+    let tracepoints = tracepoints_or_default(tracepoints);
+    let breakdown = !kprobe && tracepoints.len() > 1;
+    let key = |reason: &str| match breakdown {
+        true => format!("probe,{location},{reason}"),
+        false => format!("{location},{reason}"),
+    };
+    let (with, without) = split_by_reason(&tracepoints);
+    let probes = match kprobe {
+        false => {
+            let attach = |tracepoints: &[&str]| {
+                tracepoints
+                    .iter()
+                    .map(|t| format!("tracepoint:skb:{t}"))
+                    .collect::<Vec<_>>()
+                    .join(",\n")
+            };
+            let mut probes = Vec::new();
+            if !with.is_empty() {
+                probes.push(format!(
+                    "{}
+{{
+{resolve}    @stack[{}] = count();
+}}",
+                    attach(&with),
+                    key(&lookup)
+                ));
+            }
+            if !without.is_empty() {
+                probes.push(format!(
+                    "{}
+{{
+    @stack[{}] = count();
+}}",
+                    attach(&without),
+                    key("\"-\"")
+                ));
+            }
+            probes.join("\n\n")
+        }
+        true => format!(
+            "kprobe:kfree_skb_reason
+{{
+{resolve}    @stack[{}] = count();
+}}",
+            key(&lookup)
+        ),
+    };
+
+    // bpftrace can't filter maps when printing them, loop over the entries
+    // instead.
+    let print = match (min_count, breakdown) {
+        (Some(min), false) => format!(
+            "for ($drop : @stack) {{
+        if ($drop.1 >= {min}) {{
+            printf(\"@stack[%s, %s]: %d\\n\", $drop.0.0, $drop.0.1, $drop.1);
+        }}
+    }}"
+        ),
+        (Some(min), true) => format!(
+            "for ($drop : @stack) {{
+        if ($drop.1 >= {min}) {{
+            printf(\"@stack[%s, %s, %s]: %d\\n\", $drop.0.0, $drop.0.1, $drop.0.2, $drop.1);
+        }}
+    }}"
+        ),
+        (None, _) => "print(@stack);".to_string(),
+    };
+    let bpftrace_version = bpftrace_version(min_count);
+    let (major, minor) = SCRIPT_MIN_KERNEL;
+    let requires = comment(
+        "//",
+        &format!("Requires Linux >= {major}.{minor} and bpftrace >= {bpftrace_version}."),
+        wrap_width,
+    );
+    let generated = comment("//", &generated_by(reasons), wrap_width);
+    let annotation = match annotate {
+        true => {
+            let keys = format!("@stack[{}]", bpftrace_keys(kprobe, breakdown).join(", "));
+            let location = match kprobe {
+                false => "location is the kernel function (symbol+offset) which freed the skb, ie. where it was dropped",
+                true => "stack is the kernel stack of the drop: the probed function and, below it, the one which freed the skb, ie. where it was dropped",
+            };
+            let text = [
+                format!("Drops are counted in {keys}, printed and reset every 5 seconds."),
+                match !kprobe && !without.is_empty() {
+                    false => format!("{location}, and reason the name of the drop reason."),
+                    true => format!("{location}, and reason the name of the drop reason (- for tracepoints not reporting one)."),
+                },
+                "Entries count the drops seen in the interval for a given key: the top ones point to the code paths dropping the most packets.".to_string(),
+            ];
+            text.iter()
+                .map(|t| comment("//", t, wrap_width))
+                .fold(String::from("\n"), |out, t| out + &t + "\n")
+        }
+        false => String::new(),
+    };
+    let exit = match duration {
+        Some(secs) => format!(
+            "
+interval:s:{secs}
+{{
+    exit();
+}}
+"
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "#!/usr/bin/bpftrace
+{requires}
+{generated}
+{annotation}
+BEGIN
+{{
+{reasons_def}    printf(\"Tracing dropped skbs... Hit Ctrl-C to end.\\n\");
+}}
+
+{probes}
+
+interval:s:5
+{{
+    time(\"%F %T %z (%Z)\\n\");
+    {print}
+    printf(\"\\n\");
+    clear(@stack);
+}}
+{exit}
+END
+{{
+  clear(@stack);{cleanup}
+}}"
+    )
+}
+
+// Minimum bpftrace version required by the monitoring scripts. Looping over
+// maps (to filter on min_count) requires bpftrace 0.21.
+fn bpftrace_version(min_count: Option<u64>) -> &'static str {
+    match min_count {
+        Some(_) => "0.21",
+        None => "0.9",
+    }
+}
+
+// Keys of the @stack map the bpftrace monitoring scripts count drops in.
+fn bpftrace_keys(kprobe: bool, breakdown: bool) -> &'static [&'static str] {
+    match (kprobe, breakdown) {
+        (false, false) => &["location", "reason"],
+        (false, true) => &["tracepoint", "location", "reason"],
+        (true, _) => &["stack", "reason"],
+    }
+}
+
+/// Formats a JSON manifest describing the script generated by [`bpftrace`] from
+/// the same inputs, for tools running it to interpret its output without
+/// parsing the script: its requirements, the probes attached, the map drops are
+/// counted in and its keys, the reporting interval and duration (in seconds),
+/// the `opts.min_count` filter and the map of raw values to reason names.
+pub fn bpftrace_json(reasons: &DropReasons, opts: &Opts) -> Result<String> {
+    let Opts {
+        min_count,
+        ref tracepoints,
+        duration,
+        ..
+    } = *opts;
+    bpftrace_manifest(reasons, min_count, tracepoints, duration, false)
+}
+
+/// Formats a JSON manifest describing the script generated by
+/// [`bpftrace_kprobe`], as [`bpftrace_json`].
+pub fn bpftrace_kprobe_json(reasons: &DropReasons, opts: &Opts) -> Result<String> {
+    let Opts {
+        min_count,
+        duration,
+        ..
+    } = *opts;
+    bpftrace_manifest(reasons, min_count, &[], duration, true)
+}
+
+// Formats the JSON manifest of a bpftrace monitoring script (see
+// bpftrace_json), attaching to kfree_skb_reason if `kprobe` is set.
+fn bpftrace_manifest(
+    reasons: &DropReasons,
+    min_count: Option<u64>,
+    tracepoints: &[String],
+    duration: Option<u64>,
+    kprobe: bool,
+) -> Result<String> {
+    #[derive(Serialize)]
+    struct Manifest<'a> {
+        requires: Requires,
+        probes: Vec<String>,
+        map: &'static str,
+        keys: &'static [&'static str],
+        interval: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        duration: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        min_count: Option<u64>,
+        reasons: BTreeMap<u32, &'a str>,
+    }
+
+    #[derive(Serialize)]
+    struct Requires {
+        linux: String,
+        bpftrace: &'static str,
+    }
+
+    let tracepoints = tracepoints_or_default(tracepoints);
+    let probes = match kprobe {
+        false => tracepoints
+            .iter()
+            .map(|t| format!("tracepoint:skb:{t}"))
+            .collect(),
+        true => vec!["kprobe:kfree_skb_reason".to_string()],
+    };
+    let (major, minor) = SCRIPT_MIN_KERNEL;
+
+    Ok(serde_json::to_string_pretty(&Manifest {
+        requires: Requires {
+            linux: format!("{major}.{minor}"),
+            bpftrace: bpftrace_version(min_count),
+        },
+        probes,
+        map: "@stack",
+        keys: bpftrace_keys(kprobe, !kprobe && tracepoints.len() > 1),
+        interval: 5,
+        duration,
+        min_count,
+        reasons: reasons.by_value().collect(),
+    })?)
+}
+
+/// Constructs a bpftrace script monitoring drop reasons and reporting them as
+/// raw values, for them to be translated while the script is running.
+pub fn bpftrace_watch() -> String {
+    "BEGIN
+{
+    printf(\"Tracing dropped skbs... Hit Ctrl-C to end.\\n\");
+}
+
+tracepoint:skb:kfree_skb
+{
+    @drops[ksym(args->location),args->reason] = count();
+}
+
+interval:s:5
+{
+    time(\"%F %T %z (%Z)\\n\");
+    print(@drops);
+    printf(\"\\n\");
+    clear(@drops);
+}
+
+END
+{
+  clear(@drops);
+}"
+    .to_string()
+}
+
+/// Constructs a stap script to monitor drop reasons. If `opts.min_count` is
+/// set, only the drops seen at least that many times in an interval are
+/// reported. The script probes the skb tracepoints of `opts.tracepoints`, or
+/// `kfree_skb` if none is given. When probing more than one tracepoint, drops
+/// are also broken down by tracepoint. Only `kfree_skb` reports a drop reason:
+/// drops seen on the other tracepoints are counted without one. Comments are
+/// wrapped at `opts.wrap_width` columns, if set. If `opts.duration` is set, the
+/// script exits on its own after that many seconds.
+pub fn stap(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        ref tracepoints,
+        wrap_width,
+        duration,
+        ..
+    } = *opts;
+    let tracepoints = tracepoints_or_default(tracepoints);
+    let breakdown = tracepoints.len() > 1;
+    let (with, without) = split_by_reason(&tracepoints);
+    let probe = |tracepoints: &[&str], reason| {
+        let probes = tracepoints
+            .iter()
+            .map(|t| format!("kernel.trace(\"{t}\")"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let key = match breakdown {
+            true => format!("pn(), $location, {reason}"),
+            false => format!("$location, {reason}"),
+        };
+        format!(
+            "probe {probes} {{
+    skb_drop_reason[{key}] <<< 1;
+}}"
+        )
+    };
+    let mut probes = Vec::new();
+    if !with.is_empty() {
+        probes.push(probe(&with, "$reason"));
+    }
+    if !without.is_empty() {
+        probes.push(probe(&without, "-1"));
+        probes.push(
+            "# Drops seen on tracepoints not reporting a reason are counted under -1.
+probe begin {
+    drop_reasons[-1] = \"-\";
+}"
+            .to_string(),
+        );
+    }
+
+    stap_script(
+        reasons,
+        min_count,
+        breakdown,
+        wrap_width,
+        duration,
+        &probes.join("\n\n"),
+        false,
+    )
+}
+
+// Describes what a generated script covers, for users to check it matches
+// their kernel.
+fn generated_by(reasons: &DropReasons) -> String {
+    format!(
+        "Generated by drdump: {} reasons across {} sub-systems (fingerprint {:016x}).",
+        reasons.len(),
+        reasons.count_by_subsys().len(),
+        reasons.fingerprint()
+    )
+}
+
+// Formats a comment, wrapping it at `width` columns (including the comment
+// `prefix`) if set. Words longer than the width are not split.
+fn comment(prefix: &str, text: &str, width: Option<usize>) -> String {
+    let width = match width {
+        Some(width) => width,
+        None => return format!("{prefix} {text}"),
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(format!("{prefix} {word}")),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Constructs Rust code defining a `DROP_REASONS` static perfect hash map
+/// (using the `phf` crate) from raw values to drop reason names.
+#[cfg(feature = "rust-phf")]
+pub fn rust_phf(reasons: &DropReasons) -> String {
+    let mut map = phf_codegen::Map::new();
+    reasons.by_value().for_each(|(val, name)| {
+        map.entry(val, &format!("{name:?}"));
+    });
+
+    format!(
+        "// Generated by drdump, requires the phf crate.
+pub static DROP_REASONS: phf::Map<u32, &'static str> = {};",
+        map.build()
+    )
+}
+
+// Returns the given skb tracepoints, or kfree_skb if none is given.
+fn tracepoints_or_default(tracepoints: &[String]) -> Vec<&str> {
+    match tracepoints.is_empty() {
+        true => vec!["kfree_skb"],
+        false => tracepoints.iter().map(String::as_str).collect(),
+    }
+}
+
+// Skb tracepoints reporting a drop reason. Drops seen on the others (eg.
+// consume_skb) are counted without one.
+const REASON_TRACEPOINTS: &[&str] = &["kfree_skb"];
+
+// Splits the given skb tracepoints between the ones reporting a drop reason and
+// the others.
+fn split_by_reason<'a>(tracepoints: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    tracepoints
+        .iter()
+        .copied()
+        .partition(|t| REASON_TRACEPOINTS.contains(t))
+}
+
+/// Constructs a stap script to monitor drop reasons, probing the kernel
+/// functions freeing skbs with a reason instead of the `kfree_skb` tracepoint,
+/// for kernels where the latter is not available to stap. Comments are wrapped
+/// at `opts.wrap_width` columns, if set. If `opts.duration` is set, the script
+/// exits on its own after that many seconds.
+pub fn stap_legacy(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        wrap_width,
+        duration,
+        ..
+    } = *opts;
+    stap_script(
+        reasons,
+        min_count,
+        false,
+        wrap_width,
+        duration,
+        "probe kernel.function(\"sk_skb_reason_drop\") !, kernel.function(\"kfree_skb_reason\") {
+    skb_drop_reason[caller_addr(), $reason] <<< 1;
+}",
+        false,
+    )
+}
+
+/// Constructs a stap script to monitor drop reasons like [`stap_legacy`], but
+/// reading the reason from the probed function registers using embedded C, for
+/// kernels where `$reason` is not accessible (eg. lacking debuginfo). Embedded
+/// C requires guru mode (`stap -g`). Comments are wrapped at `opts.wrap_width`
+/// columns, if set. If `opts.duration` is set, the script exits on its own
+/// after that many seconds.
+pub fn stap_embedded(reasons: &DropReasons, opts: &Opts) -> String {
+    let Opts {
+        min_count,
+        wrap_width,
+        duration,
+        ..
+    } = *opts;
+    stap_script(
+        reasons,
+        min_count,
+        false,
+        wrap_width,
+        duration,
+        "%{
+#include <linux/ptrace.h>
+%}
+
+# Reads the n-th argument (from 0) of the probed function.
+function reason_arg:long(n:long) %{ /* pure */
+    STAP_RETVALUE = CONTEXT->kregs ? regs_get_kernel_argument(CONTEXT->kregs, STAP_ARG_n) : 0;
+%}
+
+probe kernel.function(\"sk_skb_reason_drop\") ? {
+    skb_drop_reason[caller_addr(), reason_arg(2)] <<< 1;
+}
+
+probe kernel.function(\"kfree_skb_reason\") ? {
+    skb_drop_reason[caller_addr(), reason_arg(1)] <<< 1;
+}",
+        true,
+    )
+}
+
+// Constructs a stap script reporting the drop reasons collected by the given
+// probe, filtering out the drops seen less than `min_count` times. If
+// `breakdown` is set, the probe also indexes drops by the probe name. If
+// `duration` is set, the script exits after that many seconds. If `guru` is
+// set, the script is run in guru mode, eg. for the probe to use embedded C.
+fn stap_script(
+    reasons: &DropReasons,
+    min_count: Option<u64>,
+    breakdown: bool,
+    wrap_width: Option<usize>,
+    duration: Option<u64>,
+    probe: &str,
+    guru: bool,
+) -> String {
+    let reasons_def = reasons
+        .by_value()
+        .fold(String::new(), |mut out, (val, name)| {
+            write!(out, "    drop_reasons[{val}] = \"{}\";", quote_escape(name)).unwrap();
+            if reasons.is_consume(val) == Some(true) {
+                write!(out, " # consume, not a drop").unwrap();
+            }
+            writeln!(out).unwrap();
+            out
+        });
+    let (keys, header, row) = match breakdown {
+        true => (
+            "tracepoint, location, reason",
+            "printf(\"\\n%-35s%-35s%-35s%10s\\n\",\"Tracepoint\",\"Drop\",\"Location\",\"Count\");",
+            "printf(\"%-35s%-35s%-35s%10d\\n\",tracepoint,symname(location),drop_reasons[reason],@count(skb_drop_reason[tracepoint, location, reason]))",
+        ),
+        false => (
+            "location, reason",
+            "printf(\"\\n%-35s%-35s%10s\\n\",\"Drop\",\"Location\",\"Count\");",
+            "printf(\"%-35s%-35s%10d\\n\",symname(location),drop_reasons[reason],@count(skb_drop_reason[location, reason]))",
+        ),
+    };
+    let filter = match min_count {
+        Some(min) => format!(
+            "
+        if (@count(skb_drop_reason[{keys}]) < {min}) continue"
+        ),
+        None => String::new(),
+    };
+
+    let (major, minor) = SCRIPT_MIN_KERNEL;
+    let (shebang, guru) = match guru {
+        true => (
+            "/usr/bin/stap -g",
+            " Uses embedded C: requires guru mode (stap -g).",
+        ),
+        false => ("/usr/bin/env stap", ""),
+    };
+    let requires = comment(
+        "#",
+        &format!("Requires Linux >= {major}.{minor}.{guru}"),
+        wrap_width,
+    );
+    let generated = comment("#", &generated_by(reasons), wrap_width);
+    let exit = match duration {
+        Some(secs) => format!(
+            "
+
+probe timer.s({secs}) {{
+    exit()
+}}"
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "#! {shebang}
+{requires}
+{generated}
+
+global skb_drop_reason
+global drop_reasons
+
+{probe}
+
+probe begin {{
+    printf(\"Tracing dropped skbs... Hit Ctrl-C to end.\\n\");
+}}
+
+# Report every 5 seconds
+probe timer.sec(5)
+{{
+    printf(\"\\n%s\", tz_ctime(gettimeofday_s()))
+{reasons_def}
+    {header}
+    foreach([{keys}] in skb_drop_reason) {{{filter}
+        {row}
+    }}
+    delete skb_drop_reason
+}}{exit}"
+    )
+}
+
+/// Constructs a standalone Python script resolving raw values into drop
+/// reasons, mirroring the `resolve` command behavior.
+pub fn python_cli(reasons: &DropReasons) -> Result<String> {
+    // JSON strings are valid Python string literals.
+    let mut reasons_def = String::new();
+    for (val, name) in reasons.by_value() {
+        writeln!(reasons_def, "    {val}: {},", serde_json::to_string(name)?).unwrap();
+    }
+    let mut subsys_def = String::new();
+    for (id, name) in reasons.subsystems() {
+        writeln!(subsys_def, "    {id}: {},", serde_json::to_string(name)?).unwrap();
+    }
+    let mut desc_def = String::new();
+    for (val, desc) in reasons
+        .by_value()
+        .filter_map(|(val, _)| reasons.description(val).map(|desc| (val, desc)))
+    {
+        writeln!(desc_def, "    {val}: {},", serde_json::to_string(desc)?).unwrap();
+    }
+
+    Ok(format!(
+        "#!/usr/bin/env python3
+\"\"\"Resolves raw skb drop reason values. Generated by drdump.\"\"\"
+
+import argparse
+
+DROP_REASONS = {{
+{reasons_def}}}
+
+SUBSYSTEMS = {{
+{subsys_def}}}
+
+DESCRIPTIONS = {{
+{desc_def}}}
+
+
+def format_reason(val, verbose):
+    name = DROP_REASONS.get(val)
+    if name is None:
+        name = f\"Unknown reason {{val}}\"
+        verbose = True
+    if verbose:
+        subsys = SUBSYSTEMS.get(val >> 16)
+        if subsys is not None:
+            name += f\" (sub-system: {{subsys}})\"
+        desc = DESCRIPTIONS.get(val)
+        if desc is not None:
+            name += f\": {{desc}}\"
+    return name
+
+
+def main():
+    parser = argparse.ArgumentParser(description=\"Resolve raw skb drop reason values\")
+    parser.add_argument(\"values\", metavar=\"VALUE\", type=lambda s: int(s, 0), nargs=\"+\",
+                        help=\"raw drop reason value (eg. 65538 or 0x10002)\")
+    parser.add_argument(\"-v\", \"--verbose\", action=\"store_true\",
+                        help=\"display sub-system for drop reasons\")
+    args = parser.parse_args()
+
+    for val in args.values:
+        print(format_reason(val, args.verbose))
+
+
+if __name__ == \"__main__\":
+    main()"
+    ))
+}
+
+/// How the names not fitting in the output width are displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NameFit {
+    /// Names overflow, except in tables where they are truncated.
+    #[default]
+    Overflow,
+    /// Names are truncated, ending with an ellipsis.
+    Truncate,
+    /// Names are wrapped onto continuation lines, aligned under the name
+    /// column.
+    Wrap,
+}
+
+/// Options used when rendering drop reasons with an [`OutputFormat`]. Formats
+/// ignore the options not applying to them.
+#[derive(Clone, Debug, Default)]
+pub struct Opts {
+    /// Increase verbosity (eg. display the sub-system of drop reasons).
+    pub verbose: bool,
+    /// Make the output layout stable regardless of the set of reasons.
+    pub deterministic: bool,
+    /// Display the stability of the reasons, when known.
+    pub stability: bool,
+    /// Group reasons by sub-system.
+    pub by_subsys: bool,
+    /// Align what follows the reason names on the longest one.
+    pub align_names: bool,
+    /// Only report the drops seen at least that many times in an interval in
+    /// monitoring scripts.
+    pub min_count: Option<u64>,
+    /// Skb tracepoints monitoring scripts attach to, `kfree_skb` if empty.
+    pub tracepoints: Vec<String>,
+    /// Column to wrap the comments of monitoring scripts at, if any.
+    pub wrap_width: Option<usize>,
+    /// Seconds after which monitoring scripts exit on their own, if any.
+    pub duration: Option<u64>,
+    /// Embed comments explaining the output of monitoring scripts.
+    pub annotate: bool,
+    /// Numeric base used to display raw values.
+    pub base: Base,
+    /// Draw tables using ASCII characters only.
+    pub ascii: bool,
+    /// Number of columns tables and names (see `name_fit`) are fitted in, if
+    /// any.
+    pub width: Option<usize>,
+    /// How names not fitting in `width` are displayed.
+    pub name_fit: NameFit,
+    /// Omit the sub-systems defining no drop reason.
+    pub hide_empty: bool,
+    /// Group the reasons by semantic equivalence instead of category.
+    pub group_equivalent: bool,
+}
+
+/// Output format rendering a set of drop reasons.
+pub trait OutputFormat {
+    /// Name of the format, used to select it.
+    fn name(&self) -> &str;
+    /// File name extension of the rendered output, without the leading dot.
+    fn extension(&self) -> &str {
+        "txt"
+    }
+    /// Renders the drop reasons.
+    fn render(&self, reasons: &DropReasons, opts: &Opts) -> Result<String>;
+}
+
+// Renders drop reasons using one of the functions of this module.
+type Render = fn(&DropReasons, &Opts) -> Result<String>;
+
+// Built-in output format.
+struct Builtin {
+    name: &'static str,
+    extension: &'static str,
+    render: Render,
+}
+
+impl OutputFormat for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn render(&self, reasons: &DropReasons, opts: &Opts) -> Result<String> {
+        match LABELLED.contains(&self.name) {
+            true => (self.render)(&reasons.labelled(), opts),
+            false => (self.render)(reasons, opts),
+        }
+    }
+}
+
+// Built-in formats displaying the labels of renamed reasons instead of their
+// kernel names (see DropReasons::labelled), being read by humans or printing
+// the names at runtime. Other formats are machine readable or match the kernel
+// names (eg. in logs) and keep them.
+const LABELLED: &[&str] = &[
+    "raw",
+    "raw-compact",
+    "tree",
+    "table",
+    "categories",
+    "subsys-count",
+    "rst",
+    "latex",
+    "suricata",
+    "awk",
+    "powershell",
+    "perf-probe",
+    "python-cli",
+    "bpftrace",
+    "bpftrace-switch",
+    "bpftrace-kprobe",
+    "stap",
+    "stap-legacy",
+    "stap-embedded",
+];
+
+/// Registry of output formats, looked up by name.
+pub struct Registry {
+    formats: Vec<Box<dyn OutputFormat>>,
+}
+
+impl Registry {
+    /// Builds a registry without any format.
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Builds a registry holding all the built-in formats.
+    pub fn with_builtins() -> Self {
+        let builtins: &[(&'static str, &'static str, Render)] = &[
+            ("raw", "txt", |r, o| {
+                let raw = raw(r, o);
+                Ok(match o.width {
+                    Some(width) => fit_names(&raw, width, o.name_fit, o.ascii),
+                    None => raw,
+                })
+            }),
+            ("raw-compact", "tsv", |r, o| Ok(raw_compact(r, o))),
+            ("json", "json", |r, _| json(r)),
+            ("flat-json", "flat.json", |r, _| json_flat(r)),
+            ("by-name-json", "by-name.json", |r, _| json_by_name(r)),
+            ("facts", "facts.json", |r, _| facts(r)),
+            ("properties", "properties", |r, _| Ok(properties(r))),
+            ("yaml-subsys", "yaml", |r, _| yaml_subsys(r)),
+            ("influx", "lp", |r, _| Ok(influx(r))),
+            ("cue", "cue", |r, _| cue(r)),
+            ("journald", "journald.txt", |r, _| Ok(journald(r))),
+            ("perfetto", "perfetto.json", |r, _| perfetto(r)),
+            ("xml", "xml", |r, _| Ok(xml(r))),
+            ("rst", "rst", |r, _| Ok(rst(r))),
+            ("latex", "tex", |r, _| Ok(latex(r))),
+            ("subsys-count", "subsys-count.txt", |r, o| {
+                Ok(subsys_count(r, o))
+            }),
+            ("subsys-count-json", "subsys-count.json", subsys_count_json),
+            ("env-json", "env.json", |r, _| env_json(r)),
+            ("tree", "tree.txt", |r, o| Ok(tree(r, o))),
+            ("vim", "vim", |r, _| Ok(vim(r))),
+            ("awk", "awk", |r, _| Ok(awk(r))),
+            ("powershell", "ps1", |r, _| Ok(powershell(r))),
+            ("kmsg-grep", "kmsg-grep.txt", |r, _| kmsg_grep(r)),
+            ("suricata", "rules.txt", |r, _| Ok(suricata(r))),
+            ("hcl", "tf", |r, _| Ok(hcl(r))),
+            ("openmetrics", "om.txt", |r, _| Ok(openmetrics(r))),
+            ("clickhouse", "clickhouse.sql", |r, _| Ok(clickhouse(r))),
+            ("perf-probe", "perf.sh", |r, _| Ok(perf_probe(r))),
+            ("table", "table.txt", |r, o| Ok(table(r, o))),
+            ("categories", "categories.txt", |r, o| Ok(categories(r, o))),
+            ("categories-json", "categories.json", categories_json),
+            ("bpftrace", "bt", |r, o| Ok(bpftrace(r, o))),
+            ("bpftrace-switch", "switch.bt", |r, o| {
+                Ok(bpftrace_switch(r, o))
+            }),
+            ("bpftrace-kprobe", "kprobe.bt", |r, o| {
+                Ok(bpftrace_kprobe(r, o))
+            }),
+            ("bpftrace-json", "bt.json", bpftrace_json),
+            (
+                "bpftrace-kprobe-json",
+                "kprobe.bt.json",
+                bpftrace_kprobe_json,
+            ),
+            ("stap", "stp", |r, o| Ok(stap(r, o))),
+            ("stap-legacy", "legacy.stp", |r, o| Ok(stap_legacy(r, o))),
+            ("stap-embedded", "embedded.stp", |r, o| {
+                Ok(stap_embedded(r, o))
+            }),
+            ("python-cli", "py", |r, _| python_cli(r)),
+            ("c-enum", "h", |r, _| Ok(c_enum(r))),
+        ];
+
+        let mut registry = Self::new();
+        builtins.iter().for_each(|&(name, extension, render)| {
+            registry.register(Box::new(Builtin {
+                name,
+                extension,
+                render,
+            }));
+        });
+        #[cfg(feature = "ron")]
+        registry.register(Box::new(Builtin {
+            name: "ron",
+            extension: "ron",
+            render: |r, _| ron(r),
+        }));
+        #[cfg(feature = "rust-phf")]
+        registry.register(Box::new(Builtin {
+            name: "rust-phf",
+            extension: "rs",
+            render: |r, _| Ok(rust_phf(r)),
+        }));
+        registry
+    }
+
+    /// Registers a format. A format already registered with the same name is
+    /// replaced.
+    pub fn register(&mut self, format: Box<dyn OutputFormat>) -> &mut Self {
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(format);
+        self
+    }
+
+    /// Returns a format given its name, if registered.
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormat> {
+        self.formats
+            .iter()
+            .find(|f| f.name() == name)
+            .map(|f| f.as_ref())
+    }
+
+    /// Iterates over the names of the registered formats, in registration
+    /// order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|f| f.name())
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,75 @@
+//! Kernel versions the core drop reasons first appeared in, curated from the
+//! history of the kernel's include/net/dropreason-core.h (and of
+//! include/linux/skbuff.h before it). The index is partial: reasons missing
+//! from it are not known, rather than guessed.
+
+// Sorted by name, for binary search lookups.
+const FIRST_SEEN: &[(&str, &str)] = &[
+    ("SKB_DROP_REASON_BPF_CGROUP_EGRESS", "5.18"),
+    ("SKB_DROP_REASON_CPU_BACKLOG", "5.18"),
+    ("SKB_DROP_REASON_DEV_HDR", "5.18"),
+    ("SKB_DROP_REASON_DEV_READY", "5.18"),
+    ("SKB_DROP_REASON_FULL_RING", "5.18"),
+    ("SKB_DROP_REASON_HDR_TRUNC", "5.18"),
+    ("SKB_DROP_REASON_IPV6DISABLED", "5.18"),
+    ("SKB_DROP_REASON_IP_CSUM", "5.17"),
+    ("SKB_DROP_REASON_IP_INHDR", "5.17"),
+    ("SKB_DROP_REASON_IP_NOPROTO", "5.18"),
+    ("SKB_DROP_REASON_IP_OUTNOROUTES", "5.18"),
+    ("SKB_DROP_REASON_IP_RPFILTER", "5.17"),
+    ("SKB_DROP_REASON_NEIGH_CREATEFAIL", "5.18"),
+    ("SKB_DROP_REASON_NEIGH_DEAD", "5.18"),
+    ("SKB_DROP_REASON_NEIGH_FAILED", "5.18"),
+    ("SKB_DROP_REASON_NEIGH_QUEUEFULL", "5.18"),
+    ("SKB_DROP_REASON_NETFILTER_DROP", "5.17"),
+    ("SKB_DROP_REASON_NOMEM", "5.18"),
+    ("SKB_DROP_REASON_NOT_SPECIFIED", "5.17"),
+    ("SKB_DROP_REASON_NO_SOCKET", "5.17"),
+    ("SKB_DROP_REASON_OTHERHOST", "5.17"),
+    ("SKB_DROP_REASON_PKT_TOO_SMALL", "5.17"),
+    ("SKB_DROP_REASON_PROTO_MEM", "5.18"),
+    ("SKB_DROP_REASON_QDISC_DROP", "5.18"),
+    ("SKB_DROP_REASON_SKB_CSUM", "5.18"),
+    ("SKB_DROP_REASON_SKB_GSO_SEG", "5.18"),
+    ("SKB_DROP_REASON_SKB_UCOPY_FAULT", "5.18"),
+    ("SKB_DROP_REASON_SOCKET_BACKLOG", "5.18"),
+    ("SKB_DROP_REASON_SOCKET_FILTER", "5.17"),
+    ("SKB_DROP_REASON_SOCKET_RCVBUFF", "5.18"),
+    ("SKB_DROP_REASON_TAP_FILTER", "5.18"),
+    ("SKB_DROP_REASON_TAP_TXFILTER", "5.18"),
+    ("SKB_DROP_REASON_TCP_ACK_UNSENT_DATA", "5.19"),
+    ("SKB_DROP_REASON_TCP_CLOSE", "5.19"),
+    ("SKB_DROP_REASON_TCP_CSUM", "5.17"),
+    ("SKB_DROP_REASON_TCP_FASTOPEN", "5.19"),
+    ("SKB_DROP_REASON_TCP_FLAGS", "5.18"),
+    ("SKB_DROP_REASON_TCP_INVALID_SEQUENCE", "5.19"),
+    ("SKB_DROP_REASON_TCP_INVALID_SYN", "5.19"),
+    ("SKB_DROP_REASON_TCP_MD5FAILURE", "5.18"),
+    ("SKB_DROP_REASON_TCP_MD5NOTFOUND", "5.18"),
+    ("SKB_DROP_REASON_TCP_MD5UNEXPECTED", "5.18"),
+    ("SKB_DROP_REASON_TCP_OFOMERGE", "5.18"),
+    ("SKB_DROP_REASON_TCP_OFO_DROP", "5.19"),
+    ("SKB_DROP_REASON_TCP_OFO_QUEUE_PRUNE", "5.19"),
+    ("SKB_DROP_REASON_TCP_OLD_ACK", "5.19"),
+    ("SKB_DROP_REASON_TCP_OLD_DATA", "5.18"),
+    ("SKB_DROP_REASON_TCP_OVERWINDOW", "5.18"),
+    ("SKB_DROP_REASON_TCP_RESET", "5.19"),
+    ("SKB_DROP_REASON_TCP_RFC7323_PAWS", "5.19"),
+    ("SKB_DROP_REASON_TCP_TOO_OLD_ACK", "5.19"),
+    ("SKB_DROP_REASON_TCP_ZEROWINDOW", "5.18"),
+    ("SKB_DROP_REASON_TC_EGRESS", "5.18"),
+    ("SKB_DROP_REASON_TC_INGRESS", "5.18"),
+    ("SKB_DROP_REASON_UDP_CSUM", "5.17"),
+    ("SKB_DROP_REASON_UNICAST_IN_L2_MULTICAST", "5.17"),
+    ("SKB_DROP_REASON_XDP", "5.18"),
+    ("SKB_DROP_REASON_XFRM_POLICY", "5.18"),
+];
+
+/// Returns the kernel version (eg. `5.19`) a drop reason first appeared in
+/// given its name, if known.
+pub fn first_seen(name: &str) -> Option<&'static str> {
+    FIRST_SEEN
+        .binary_search_by(|(n, _)| n.cmp(&name))
+        .ok()
+        .map(|i| FIRST_SEEN[i].1)
+}
@@ -0,0 +1,36 @@
+//! Library to work with skb drop reasons given a set of BTF files.
+//!
+//! The main entry point is [`DropReasons`], which holds the drop reasons and
+//! sub-systems found in a set of BTF files and provides helpers to resolve and
+//! iterate over them.
+//!
+//! Without the default `std` feature, the library is `no_std` (only requiring
+//! `alloc`) and only provides the [`table`] module, to translate raw values
+//! using static tables.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "aliases")]
+pub mod aliases;
+#[cfg(feature = "descriptions")]
+pub mod description;
+#[cfg(feature = "equivalence")]
+pub mod equivalence;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "offline-db")]
+pub mod offline;
+#[cfg(feature = "std")]
+mod reasons;
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "std")]
+pub mod stability;
+pub mod table;
+
+#[cfg(feature = "std")]
+pub use reasons::*;
@@ -1,17 +1,25 @@
-use std::{collections::BTreeMap, fmt::Write, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
 
 use anyhow::{bail, Result};
-use btf_rs::{utils::BtfCollection, Type};
-use clap::{builder::PossibleValuesParser, Parser};
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
 
-// Keep this in-sync with the kernel definition in include/net/dropreason.h
-//
-// Used to detect if the kernel supports more drop reasons than we know of.
-const SKB_DROP_REASON_SUBSYS_NUM: usize = 5;
+use drdump::{format, source, Base, Decoded, DropReason, DropReasons, MergeStrategy, ReasonDiff};
 
-// Known drop reason definitions in the kernel (except for core that is
-// mandatory).
-const NON_CORE_DROP_REASONS: &[&str] = &["mac80211_drop_reason", "ovs_drop_reason"];
+// Directory where the running kernel exposes its BTF files.
+const RUNNING_BTF: &str = "/sys/kernel/btf";
+// Format files of the kfree_skb tracepoint, for the possible tracefs mount
+// points.
+const TRACEFS_KFREE_SKB: &[&str] = &[
+    "/sys/kernel/tracing/events/skb/kfree_skb/format",
+    "/sys/kernel/debug/tracing/events/skb/kfree_skb/format",
+];
 
 #[derive(Parser)]
 #[command(
@@ -21,224 +29,2485 @@ const NON_CORE_DROP_REASONS: &[&str] = &["mac80211_drop_reason", "ovs_drop_reaso
     long_about = None,
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
     #[arg(
         long,
-        default_value = "/sys/kernel/btf",
+        global = true,
+        default_value = RUNNING_BTF,
         help = "Directory where BTF files are stored"
     )]
     btf: PathBuf,
+    #[arg(
+        long,
+        global = true,
+        help = "Only load the module BTF files (from --btf) matching the given glob pattern. Can be used multiple times. vmlinux is always loaded"
+    )]
+    btf_glob: Vec<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        conflicts_with_all = ["from_tracefs", "core_reasons_only"],
+        help = "BTF file (eg. vmlinux or a module name, from --btf) whose definitions win when an enum is defined in more than one BTF file with different members. By default vmlinux is preferred, then the definition with the most members"
+    )]
+    prefer_btf: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Report on stderr how many BTF files were loaded so far, if it is a terminal"
+    )]
+    progress: bool,
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["btf", "btf_glob"],
+        help = "Read the core drop reasons from the kfree_skb tracepoint format in tracefs instead of BTF, eg. when BTF is not available. Names lack their SKB_DROP_REASON_ prefix and sub-systems are not known"
+    )]
+    from_tracefs: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        conflicts_with_all = ["btf", "btf_glob", "from_tracefs", "prefer_btf", "core_reasons_only"],
+        help = "Same as --from-tracefs, reading the given tracepoint format file (eg. a copy of events/skb/kfree_skb/format). Names are the exact ones printed by the kernel and reasons are reported as printk-derived"
+    )]
+    from_printk_format: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["btf_glob", "from_tracefs"],
+        help = "Only load the core drop reasons from the vmlinux BTF file, skipping the module BTF files, the non-core reasons and the sub-systems. This is the fastest mode"
+    )]
+    core_reasons_only: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Keep the SKB_DROP_REASON_SUBSYS_MASK member of the core enum, which is not a real drop reason and removed by default, eg. to see the enum as BTF defines it"
+    )]
+    keep_mask: bool,
+    #[arg(
+        long,
+        global = true,
+        value_parser = PossibleValuesParser::new(["dec", "hex"]),
+        default_value = "dec",
+        help = "Numeric base used to display raw values (JSON always uses decimal)"
+    )]
+    base: String,
     #[arg(
         short,
         long,
-        help = "Resolve given value into a drop reason enum value"
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "Increase verbosity (eg. display sub-system for drop reasons)"
     )]
-    resolve: Option<u32>,
+    verbose: u8,
     #[arg(
         short,
         long,
-        value_parser = PossibleValuesParser::new(["raw", "bpftrace", "stap"]),
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "Print debug logs, and trace logs with -dd"
+    )]
+    debug: u8,
+    #[arg(
+        long,
+        global = true,
+        help = "JSON file mapping raw values to custom drop reason names, merged with the ones found in BTF"
+    )]
+    overrides: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        requires = "overrides",
+        help = "Let overrides replace already known drop reasons"
+    )]
+    force: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "VALUE=LABEL",
+        value_parser = parse_rename,
+        help = "Display a drop reason using a custom label, in the human readable outputs and generated scripts. Other outputs, comparisons and fingerprints use the kernel name, the JSON output reporting the label separately. Can be used multiple times"
+    )]
+    rename: Vec<(u32, String)>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "JSON file mapping raw values to labels, as --rename (which takes precedence)"
+    )]
+    rename_file: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ID=LABEL",
+        value_parser = parse_rename,
+        help = "Display a sub-system, given its id, using a custom label (eg. 3=mac80211). The JSON output keeps the kernel name. Can be used multiple times"
+    )]
+    subsys_label: Vec<(u32, String)>,
+    #[arg(
+        long,
+        global = true,
+        help = "Report the sub-system drop reasons not added because their value was already defined"
+    )]
+    report_duplicates: bool,
+    #[arg(
+        long,
+        global = true,
+        default_value = "keep-first",
+        value_parser = PossibleValuesParser::new(["keep-first", "prefer-subsys", "error"]),
+        help = "How to handle sub-system drop reasons reusing an already defined value: keep-first keeps the first definition (usually the core one), prefer-subsys replaces it with the sub-system one and error aborts"
+    )]
+    merge_strategy: String,
+    #[arg(
+        long,
+        global = true,
+        help = "Exit with an error, after printing the output, if the kernel defines more sub-systems than drdump knows of"
+    )]
+    fail_on_unknown_subsys: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Fail instead of warning when the value of an enum member found in BTF does not fit in 32 bits (such members are skipped otherwise)"
+    )]
+    strict: bool,
+    #[cfg(feature = "offline-db")]
+    #[arg(
+        long,
+        global = true,
+        help = "Use the drop reasons bundled in drdump if BTF files can't be loaded"
+    )]
+    offline: bool,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Dump the drop reasons (default)
+    Dump(DumpArgs),
+    /// Resolve raw values into drop reasons
+    Resolve(ResolveArgs),
+    /// Break raw values into their sub-system (VALUE & 0xffff0000) and reason
+    /// index (VALUE & 0xffff) parts, resolving each of them
+    Decode(DecodeArgs),
+    /// Display how many drop reasons each sub-system defines
+    ListSubsys(ListSubsysArgs),
+    /// Group the drop reasons by category, derived from their names (eg. TCP)
+    Categories(CategoriesArgs),
+    /// Compare the drop reasons with the ones of another set of BTF files, of
+    /// the running kernel or of a baseline file
+    Diff(DiffArgs),
+    /// Display which drop reasons are found in several sets of BTF files
+    Matrix(MatrixArgs),
+    /// Compare the core drop reason names with the ones declared in a kernel
+    /// header (include/net/dropreason-core.h), failing on mismatch
+    ValidateHeader(ValidateHeaderArgs),
+    /// Generate a monitoring script or code embedding the drop reasons
+    Generate(GenerateArgs),
+    /// Run a bpftrace monitoring script and translate the drop reasons it
+    /// reports (requires bpftrace)
+    Watch(WatchArgs),
+    /// Explain a single drop reason: its value, sub-system and description
+    Explain(ReasonArgs),
+    /// Exit successfully if a drop reason is known, with an error otherwise.
+    /// Nothing is printed
+    Exists(ReasonArgs),
+    /// Dump the members of a kernel enum exactly as found in BTF, without
+    /// masking, merging nor truncating values, eg. to debug BTF parsing
+    RawEnum(RawEnumArgs),
+    /// List the drop reasons of a sub-system exactly as defined by its own
+    /// enum, including the ones hidden when merged with the core reasons
+    SubsysReasons(SubsysReasonsArgs),
+    /// Annotate the raw drop reasons found in text read from stdin or a
+    /// followed file (eg. logs) with their names, passing everything else
+    /// through unchanged
+    #[cfg(feature = "log-filter")]
+    FilterLog(FilterLogArgs),
+    /// Print the kernel version a core drop reason first appeared in, from an
+    /// embedded index, eg. to know the minimum kernel defining it
+    #[cfg(feature = "history")]
+    FirstSeen(FirstSeenArgs),
+}
+
+// Options filtering the drop reasons to output.
+#[derive(clap::Args)]
+struct Filters {
+    #[arg(
+        long,
+        help = "Only list drop reasons of the sub-system matching the given id"
+    )]
+    subsys_id: Option<u32>,
+    #[arg(
+        long,
+        requires = "subsys_id",
+        help = "Also list the core drop reasons when filtering on a sub-system"
+    )]
+    with_core: bool,
+    #[arg(
+        long,
+        value_parser = parse_filter,
+        help = "Only list drop reasons matching all the comma separated predicates: subsys:<NAME>, name~<SUBSTRING> or value<OP><VALUE> (OP is one of =, <, <=, >, >=)"
+    )]
+    filter: Option<Filter>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_value,
+        help = "Only list drop reasons with a raw value greater or equal to N (eg. 0x10000)"
+    )]
+    since_value: Option<u32>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = parse_value,
+        help = "Only list drop reasons with a raw value lower or equal to N"
+    )]
+    until_value: Option<u32>,
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Only list drop reasons of the sub-systems whose id is greater or equal to ID (the core reasons having id 0)"
+    )]
+    min_subsys_id: Option<u32>,
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Only list drop reasons of the sub-systems whose id is lower or equal to ID"
+    )]
+    max_subsys_id: Option<u32>,
+}
+
+// Also a parser, for the default (no subcommand) options to be built.
+#[derive(Parser)]
+struct DumpArgs {
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new([
+            "raw",
+            "raw-compact",
+            "json",
+            "flat-json",
+            "by-name-json",
+            "facts",
+            "env-json",
+            "table",
+            "tree",
+            "vim",
+            "awk",
+            "powershell",
+            "kmsg-grep",
+            "suricata",
+            "properties",
+            "hcl",
+            "yaml-subsys",
+            "influx",
+            "openmetrics",
+            "clickhouse",
+            "cue",
+            "journald",
+            "perf-probe",
+            "perfetto",
+            "xml",
+            "rst",
+            "latex",
+            #[cfg(feature = "msgpack")]
+            "msgpack",
+            #[cfg(feature = "cbor")]
+            "cbor",
+            #[cfg(feature = "ron")]
+            "ron",
+        ]),
         default_value = "raw",
         help = "Format to output the drop reason values:
 - raw: output on stdout all the drop reasons that were found
-- bpftrace: construct a bpftrace monitoring script
-- stap: construct a system-tap monitoring script
+- raw-compact: output the drop reasons as tab separated value and name, without padding
+- table: output the drop reasons as a table of their value, name and sub-system with box-drawing borders (see --ascii), truncating names to fit in the terminal width when writing to one (COLUMNS overrides the width)
+- tree: output the drop reasons as a tree, sub-systems (core for the core reasons) being the top-level nodes with their reasons beneath (see --ascii)
+- vim: output a Vim syntax file highlighting the drop reason names (eg. in logs), to be sourced
+- awk: output an awk program fragment defining a drop_reason_name(val) function translating decimal raw values, eg. to be included with awk -f
+- powershell: output a PowerShell script defining a $DropReasons hashtable mapping raw values to names, eg. to be dot-sourced
+- kmsg-grep: output an extended regular expression matching any drop reason name, eg. dmesg | grep -E \"$(drdump dump -f kmsg-grep)\" (see --filter to narrow it)
+- suricata: output \"# reason <VALUE> = <NAME>\" comment lines to paste in Suricata (or other IDS) rule files
+- json: output the drop reasons as JSON
+- flat-json: output the drop reasons as a JSON object mapping raw values to names
+- by-name-json: output the drop reasons as a JSON object mapping names to raw values (or arrays of raw values for duplicate names)
+- facts: output the drop reasons and sub-systems as JSON facts (eg. Ansible custom facts), under a drop_reasons key
+- env-json: output a single JSON line of stable counts and flags for gating CI steps: drop_reason_count, core_reason_count, subsystem_count, has_ovs_reasons and unknown_subsystems
+- properties: output the drop reasons as a Java properties file
+- hcl: output a Terraform (HCL) locals block defining a drop_reasons map from raw values to names
+- yaml-subsys: output the drop reasons as a YAML document grouping them by sub-system
+- influx: output the drop reasons as InfluxDB line protocol points
+- openmetrics: output the drop reasons as an OpenMetrics exposition of a drop_reason_info gauge, labelled by value, name and sub-system
+- clickhouse: output ClickHouse SQL statements creating a drop_reasons table (if needed) and inserting the drop reasons, eg. to join with drop counts
+- cue: output CUE definitions constraining a reason field to the known drop reason names or values
+- journald: output journalctl commands matching the log entries reporting each drop reason, by name or as reason=<VALUE>
+- perf-probe: output perf probe and perf record commands monitoring drops through a kfree_skb_reason probe (x86_64), followed by a comment table of the drop reasons
+- perfetto: output the drop reasons as Perfetto interned strings (JSON)
+- xml: output the drop reasons as an XML document
+- rst: output the drop reasons as a reStructuredText table
+- latex: output the drop reasons as a LaTeX tabular
+- msgpack: output the drop reasons as MessagePack, using the JSON schema (requires --output and the msgpack feature)
+- cbor: output the drop reasons as CBOR, using the JSON schema (requires --output and the cbor feature)
+- ron: output the drop reasons as RON, using the JSON schema (requires the ron feature)
 ",
     )]
     format: String,
     #[arg(
         short,
         long,
-        help = "Increase verbosity (eg. display sub-system for drop reasons)"
+        value_name = "FILE",
+        required_if_eq_any([("format", "msgpack"), ("format", "cbor")]),
+        help = "Write the output to FILE instead of stdout"
     )]
-    verbose: bool,
+    output: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["format", "output", "fingerprint"],
+        help = "Write the drop reasons in several formats at once (see --formats), to DIR/drop_reasons.<ext>"
+    )]
+    output_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FORMATS",
+        value_delimiter = ',',
+        default_value = "raw,json,rst,bpftrace,stap",
+        requires = "output_dir",
+        help = "Comma separated list of the formats written with --output-dir, including the generated scripts (eg. bpftrace)"
+    )]
+    formats: Vec<String>,
+    #[arg(
+        long,
+        help = "Make the output byte-for-byte stable regardless of the set of reasons found (eg. fixed column width)"
+    )]
+    deterministic: bool,
+    #[arg(
+        long,
+        help = "Display the stability of the reasons (uapi or internal), when known. Always part of the JSON output"
+    )]
+    stability: bool,
+    #[arg(
+        long,
+        value_parser = PossibleValuesParser::new(["value", "subsys"]),
+        default_value = "value",
+        help = "Ordering of the drop reasons. subsys sorts by sub-system then by index within the sub-system, and separates sub-systems in the raw output"
+    )]
+    sort: String,
+    #[arg(
+        long,
+        help = "Align what follows the drop reason names (eg. sub-system with -v) on the longest name in the raw output"
+    )]
+    align_names: bool,
+    #[arg(
+        long,
+        help = "Display the sub-system drop reasons as <SUBSYS>/<INDEX> (eg. OPENVSWITCH/5) to emphasize the hierarchy. The JSON output keeps the kernel name and reports the compact one as a label"
+    )]
+    compact_names: bool,
+    #[arg(
+        long,
+        help = "Strip the SKB_DROP_REASON_ prefix of the core drop reason names, eg. NO_SOCKET. The JSON output keeps the kernel name and reports the stripped one as a label"
+    )]
+    strip_prefix: bool,
+    #[arg(
+        long,
+        help = "Draw the table and tree outputs using ASCII characters only"
+    )]
+    ascii: bool,
+    #[arg(
+        long,
+        help = "Truncate the drop reason names overflowing the terminal width in the raw output, ending them with an ellipsis. Tables always truncate them. Ignored when not writing to a terminal (COLUMNS overrides the terminal width)"
+    )]
+    truncate_names: bool,
+    #[arg(
+        long,
+        conflicts_with = "truncate_names",
+        help = "Wrap the drop reason names overflowing the terminal width onto continuation lines aligned under the name column, in the raw and table outputs. Ignored when not writing to a terminal (COLUMNS overrides the terminal width)"
+    )]
+    wrap_names: bool,
+    #[arg(
+        long,
+        help = "Only output the first N drop reasons. Human readable outputs (raw, raw-compact, table and tree) end with a note telling how many were shown"
+    )]
+    limit: Option<usize>,
+    #[arg(
+        long,
+        help = "Only output the first (lowest value) drop reason of those sharing the same name"
+    )]
+    dedup: bool,
+    #[arg(
+        long,
+        conflicts_with = "format",
+        help = "Output a fingerprint (hexadecimal digest) of the drop reasons and sub-systems instead, to detect changes"
+    )]
+    fingerprint: bool,
+    #[cfg(feature = "schema")]
+    #[arg(
+        long,
+        conflicts_with_all = ["format", "output_dir", "fingerprint", "counts"],
+        help = "Output the JSON Schema of the json format output instead, eg. to validate it or generate typed bindings. No BTF is needed"
+    )]
+    schema: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["output_dir", "fingerprint"],
+        help = "Annotate the drop reasons with their count from FILE, a JSON object mapping raw values to observed counts (eg. from a prior capture), and sort them by descending count. Reasons absent from FILE count 0. Only supported by the raw and json formats"
+    )]
+    counts: Option<PathBuf>,
+    #[command(flatten)]
+    filters: Filters,
+}
+
+#[derive(clap::Args)]
+struct ResolveArgs {
+    #[arg(
+        value_parser = parse_query,
+        help = "Values to resolve, either raw (in decimal or 0x prefixed hexadecimal) or relative to a sub-system (<SUBSYS>:<INDEX>). If none is given (nor --bytes or --file), queries are read from stdin until EOF: either raw values or reason names (name:<NAME>)"
+    )]
+    values: Vec<Query>,
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["raw", "json"]),
+        default_value = "raw",
+        help = "Format to output the resolved values, json is not supported when reading from stdin"
+    )]
+    format: String,
+    #[arg(
+        long,
+        value_name = "HEX",
+        value_parser = parse_bytes,
+        help = "Raw value to resolve given as 4 bytes in hexadecimal (eg. 02 00 01 00), as found in a memory dump. Can be used multiple times"
+    )]
+    bytes: Vec<[u8; 4]>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "File of raw values to resolve, one per line (- for stdin), eg. collected on a host. Can be used multiple times, see --combine"
+    )]
+    file: Vec<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with = "group_unknown",
+        help = "Output each distinct value once, with the number of times it was given (eg. across several --file), most frequent first. Values are read from stdin if none is given"
+    )]
+    combine: bool,
+    #[arg(
+        long,
+        value_parser = PossibleValuesParser::new(["little", "big"]),
+        default_value = "little",
+        help = "Byte order of the values given with --bytes"
+    )]
+    endian: String,
+    #[arg(long, help = "Only output the values not matching a known drop reason")]
+    only_unknown: bool,
+    #[arg(
+        long,
+        visible_alias = "in-subsys",
+        value_name = "SUBSYS",
+        help = "Interpret the values not encoding a sub-system (ie. lower than 65536) as indexes within SUBSYS, eg. when traces report sub-system relative values. Does not apply to <SUBSYS>:<INDEX> queries nor to queries read from stdin"
+    )]
+    assume_subsys: Option<String>,
+    #[arg(
+        long,
+        value_name = "SUBSYS",
+        conflicts_with_all = ["values", "bytes", "assume_subsys"],
+        help = "Resolve every value of SUBSYS, from its first index up to its highest known one, revealing the gaps in its numbering"
+    )]
+    all: Option<String>,
+    #[arg(
+        long,
+        help = "Prefix each resolved value with its decimal and hexadecimal forms, eg. 5 (0x00000005) = SKB_DROP_REASON_NO_SOCKET. Does not apply to queries read from stdin"
+    )]
+    show_hex: bool,
+    #[arg(
+        long,
+        conflicts_with = "only_unknown",
+        help = "Collapse the values not matching a known drop reason into a final summary line counting them by sub-system, instead of printing each of them"
+    )]
+    group_unknown: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["values", "bytes", "file", "all", "combine", "group_unknown", "only_unknown"],
+        help = "Read a JSON array of raw values from stdin (eg. [3, 196610]) and output a JSON array of their resolutions, in the same order. Elements which are not raw values resolve to null"
+    )]
+    json_stdin: bool,
+    #[arg(
+        long = "enum",
+        value_name = "NAME",
+        help = "Resolve the values against the members of the given kernel enum only (eg. ovs_drop_reason), as found in BTF, instead of the merged drop reasons"
+    )]
+    r#enum: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["enum", "json_stdin", "combine", "group_unknown"],
+        help = "Report every definition of the values in the parsed enums, as <ENUM>::<NAME>, instead of the merged drop reason. Disambiguates the values defined by more than one enum (eg. by both the core and a sub-system one)"
+    )]
+    all_enums: bool,
+}
+
+#[derive(clap::Args)]
+struct DiffArgs {
+    #[arg(
+        required_unless_present_any = ["running", "baseline"],
+        help = "Directory of the BTF files to compare with"
+    )]
+    dir: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with = "dir",
+        help = "Compare the drop reasons found in --btf with the ones of the running kernel and fail on mismatch"
+    )]
+    running: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["dir", "running"],
+        help = "Compare a baseline JSON file (as output by dump -f json) with the drop reasons found in --btf and fail on mismatch"
+    )]
+    baseline: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["raw", "json", "csv", "checkpatch"]),
+        default_value = "raw",
+        help = "Format to output the changes. csv outputs status,value,name_old,name_new rows, status being added, removed or renamed (value,name rows with --intersection). checkpatch outputs compiler-style <NAME>:<VALUE>: warning: lines for the removed and renamed drop reasons, which can break userspace, eg. for CI annotations"
+    )]
+    format: String,
+    #[arg(
+        long,
+        help = "List the drop reasons defined with the same value and name in both sets instead of the changes, eg. to build an allowlist portable across kernels. Never fails on mismatch"
+    )]
+    intersection: bool,
+    #[command(flatten)]
+    filters: Filters,
+}
+
+#[derive(clap::Args)]
+struct DecodeArgs {
+    #[arg(
+        required = true,
+        value_parser = parse_value,
+        help = "Raw values to decode (decimal or 0x prefixed hexadecimal)"
+    )]
+    values: Vec<u32>,
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["raw", "json"]),
+        default_value = "raw",
+        help = "Format to output the breakdowns, json outputs a single object if only one value is decoded, an array otherwise"
+    )]
+    format: String,
+}
+
+#[derive(clap::Args)]
+struct ListSubsysArgs {
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["raw", "json"]),
+        default_value = "raw",
+        help = "Format to output the sub-systems, json outputs an object with the total, core and per sub-system counts"
+    )]
+    format: String,
+    #[arg(
+        long,
+        help = "Omit the sub-systems defining no drop reason, eg. registered but unused ones. By default all the known sub-systems are listed"
+    )]
+    hide_empty: bool,
+}
+
+#[derive(clap::Args)]
+struct CategoriesArgs {
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["raw", "json"]),
+        default_value = "raw",
+        help = "Format to output the categories, raw lists the reasons of each category with -v"
+    )]
+    format: String,
+    #[arg(
+        long,
+        help = "Group the drop reasons reporting the same high-level cause (eg. checksum failures, whatever the protocol) instead of by category, listing the reasons of each group. Equivalences are only known with the equivalence feature, otherwise each reason is its own group"
+    )]
+    group_equivalent: bool,
+    #[command(flatten)]
+    filters: Filters,
+}
+
+#[derive(clap::Args)]
+struct ValidateHeaderArgs {
+    #[arg(help = "Header declaring enum skb_drop_reason, eg. include/net/dropreason-core.h")]
+    header: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct MatrixArgs {
+    #[arg(help = "Directory holding the BTF files of each kernel in a sub-directory")]
+    dir: PathBuf,
+    #[arg(
+        short,
+        long,
+        value_parser = PossibleValuesParser::new(["csv", "markdown"]),
+        default_value = "csv",
+        help = "Format of the matrix"
+    )]
+    format: String,
+    #[command(flatten)]
+    filters: Filters,
+}
+
+#[derive(clap::Args)]
+struct GenerateArgs {
+    #[arg(
+        value_parser = PossibleValuesParser::new([
+            "bpftrace",
+            "bpftrace-json",
+            "stap",
+            "python-cli",
+            "c-enum",
+            #[cfg(feature = "rust-phf")]
+            "rust-phf",
+        ]),
+        help = "What to generate:
+- bpftrace: a bpftrace monitoring script
+- bpftrace-json: a JSON manifest describing the bpftrace script generated with the same options (probes, map keys, interval, filters and reasons), to interpret its output
+- stap: a system-tap monitoring script
+- python-cli: a standalone Python script resolving drop reasons
+- c-enum: a C enum of the drop reasons
+- rust-phf: a Rust phf map of the drop reasons (requires the rust-phf feature)
+"
+    )]
+    lang: String,
+    #[arg(
+        long,
+        help = "Probe the kernel functions instead of the kfree_skb tracepoint in the stap script, for kernels where the latter is not available"
+    )]
+    stap_legacy: bool,
+    #[arg(
+        long,
+        conflicts_with = "stap_legacy",
+        help = "Like --stap-legacy but read the drop reason from the probed function registers using embedded C, for kernels where $reason is not accessible to stap. The script requires guru mode (stap -g)"
+    )]
+    stap_embedded: bool,
+    #[arg(
+        long,
+        default_value = "map",
+        value_parser = PossibleValuesParser::new(["map", "switch"]),
+        help = "How the bpftrace script resolves the reason names: map uses a map filled at startup, switch an if/else chain in the probe, avoiding map lookups on every drop (best suited to small sets of reasons)"
+    )]
+    bpftrace_style: String,
+    #[arg(
+        long,
+        default_value = "tracepoint",
+        value_parser = PossibleValuesParser::new(["tracepoint", "kprobe"]),
+        help = "What the bpftrace and stap scripts attach to: the kfree_skb tracepoint, or kprobes on the kernel functions freeing skbs with a reason, for kernels whose tracepoint lacks the reason (implies --stap-legacy for stap)"
+    )]
+    probe: String,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Only report the drops seen at least N times in an interval in the bpftrace and stap scripts"
+    )]
+    min_count: Option<u64>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Skb tracepoint the bpftrace and stap scripts attach to (default: kfree_skb). Can be used multiple times, drops are then also broken down by tracepoint. Only kfree_skb reports a drop reason, drops seen on the others (eg. consume_skb) are counted without one"
+    )]
+    tracepoint: Vec<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Wrap the comments of the bpftrace and stap scripts at N columns"
+    )]
+    wrap_width: Option<usize>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Make the bpftrace and stap scripts exit on their own after SECONDS, eg. for non-interactive runs"
+    )]
+    duration: Option<u64>,
+    #[arg(
+        long,
+        value_name = "VER",
+        value_parser = parse_kernel_version,
+        help = "Kernel version (eg. 5.15) the monitoring scripts will run on. Refuses to generate scripts it can't run"
+    )]
+    target_kernel: Option<(u32, u32)>,
+    #[arg(
+        long,
+        help = "Fail if a drop reason name is not a valid identifier in the generated code (c-enum)"
+    )]
+    validate_names: bool,
+    #[arg(
+        long,
+        help = "Like --validate-names but replace the invalid characters of drop reason names instead of failing"
+    )]
+    sanitize_names: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 64,
+        help = "Size in bytes, including the NUL terminator, of the strings holding the drop reason names in the bpftrace script (see BPFTRACE_MAX_STRLEN). Fails if a name does not fit, reporting the offending ones"
+    )]
+    name_buf: usize,
+    #[arg(
+        long,
+        help = "Only warn about the drop reason names not fitting in --name-buf, bpftrace then truncates them"
+    )]
+    truncate: bool,
+    #[arg(
+        long,
+        help = "Embed comments in the bpftrace script explaining how to read its output (eg. what the location of a drop is)"
+    )]
+    annotate_script: bool,
+    #[arg(
+        long,
+        help = "Only embed the first (lowest value) drop reason of those sharing the same name"
+    )]
+    dedup: bool,
+    #[arg(
+        long,
+        value_name = "SYSTEM",
+        value_parser = PossibleValuesParser::new(["cmake", "meson"]),
+        help = "Instead of the c-enum header, output a cmake (add_custom_command) or meson (custom_target) build rule regenerating it as drop_reasons.h at build time, from --btf. Only --btf, --validate-names, --sanitize-names and --dedup are forwarded"
+    )]
+    emit_build_rule: Option<String>,
+    #[command(flatten)]
+    filters: Filters,
+}
+
+#[derive(clap::Args)]
+struct WatchArgs {
+    #[arg(
+        long,
+        value_name = "VER",
+        value_parser = parse_kernel_version,
+        help = "Kernel version (eg. 5.15) the monitoring script will run on. Refuses to run a script it can't run"
+    )]
+    target_kernel: Option<(u32, u32)>,
+}
+
+#[cfg(feature = "log-filter")]
+#[derive(clap::Args)]
+struct FilterLogArgs {
+    #[arg(
+        long,
+        default_value = r"reason=(0x[[:xdigit:]]+|[0-9]+)",
+        help = "Regular expression matching the drop reasons, its first capture group being the raw value (decimal or 0x prefixed hexadecimal). Known values are followed by their name, eg. reason=2(NO_SOCKET)"
+    )]
+    pattern: String,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Follow FILE instead of reading stdin, as tail -f does: the lines appended to it are annotated as they arrive, until interrupted. FILE is read again from its start if truncated (eg. by log rotation)"
+    )]
+    follow: Option<PathBuf>,
+}
+
+#[cfg(feature = "history")]
+#[derive(clap::Args)]
+struct FirstSeenArgs {
+    #[arg(help = "Name of the drop reason, with or without the SKB_DROP_REASON_ prefix")]
+    name: String,
+}
+
+#[derive(clap::Args)]
+struct SubsysReasonsArgs {
+    #[arg(
+        help = "Name of the sub-system, with or without the SKB_DROP_REASON_SUBSYS_ prefix (eg. OPENVSWITCH)"
+    )]
+    name: String,
+}
+
+#[derive(clap::Args)]
+struct RawEnumArgs {
+    #[arg(help = "Name of the enum to dump, eg. skb_drop_reason")]
+    name: String,
+}
+
+#[derive(clap::Args)]
+struct ReasonArgs {
+    #[arg(
+        value_name = "NAME|VALUE",
+        help = "Drop reason, either its raw value (decimal or 0x prefixed hexadecimal) or its name, with or without the SKB_DROP_REASON_ prefix"
+    )]
+    reason: String,
+}
+
+// Looks up a known drop reason given its raw value or name, with or without
+// the SKB_DROP_REASON_ prefix, returning its raw value.
+fn lookup(reasons: &DropReasons, reason: &str) -> Option<u32> {
+    match parse_value(reason) {
+        Ok(val) => reasons.get(val).map(|_| val),
+        Err(_) => reasons
+            .value_of(reason)
+            .or_else(|| reasons.value_of(&format!("SKB_DROP_REASON_{}", reason.to_uppercase()))),
+    }
+}
+
+// Value to resolve, either raw or relative to a sub-system.
+#[derive(Clone)]
+enum Query {
+    Raw(u32),
+    Subsys(String, u16),
+}
+
+impl Query {
+    // Computes the raw value of the query.
+    fn value(&self, reasons: &DropReasons) -> Result<u32> {
+        Ok(match self {
+            Query::Raw(val) => *val,
+            Query::Subsys(subsys, index) => match reasons.subsys_id(subsys) {
+                Some(id) => (id << 16) | *index as u32,
+                None => bail!("Unknown sub-system {subsys}"),
+            },
+        })
+    }
+}
+
+// Parses a value to resolve, either raw (eg. 65538 or 0x10002) or relative to
+// a sub-system (eg. MAC80211_UNUSABLE:2 or MAC80211_UNUSABLE:0x2).
+fn parse_query(s: &str) -> Result<Query, String> {
+    match s.split_once(':') {
+        Some((subsys, index)) => match parse_value(index).map(u16::try_from) {
+            Ok(Ok(index)) => Ok(Query::Subsys(subsys.to_string(), index)),
+            Ok(Err(e)) => Err(format!("invalid sub-system index '{index}': {e}")),
+            Err(e) => Err(e),
+        },
+        None => parse_value(s).map(Query::Raw),
+    }
+}
+
+// Parses a raw value, either in decimal or in hexadecimal (0x prefixed).
+fn parse_value(val: &str) -> Result<u32, String> {
+    match val.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => val.parse(),
+    }
+    .map_err(|e| format!("invalid value '{val}': {e}"))
+}
+
+// Parses 4 bytes given in hexadecimal, optionally separated by whitespaces
+// and prefixed with 0x (eg. 02000100, "02 00 01 00" or 0x02000100).
+fn parse_bytes(s: &str) -> Result<[u8; 4], String> {
+    let hex: String = s.split_whitespace().collect();
+    let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+    if hex.len() != 8 || !hex.is_ascii() {
+        return Err(format!("expected 4 bytes in hexadecimal, got '{s}'"));
+    }
+
+    let mut bytes = [0; 4];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|e| format!("invalid byte '{}': {e}", &hex[i * 2..i * 2 + 2]))?;
+    }
+    Ok(bytes)
+}
+
+// Predicate of a --filter expression.
+#[derive(Clone)]
+enum Predicate {
+    Subsys(String),
+    Name(String),
+    Value(fn(&u32, &u32) -> bool, u32),
+}
+
+// Set of predicates a drop reason must all match to be listed.
+#[derive(Clone)]
+struct Filter(Vec<Predicate>);
+
+// Parses a --rename argument, as <VALUE>=<LABEL>. Labels are embedded in
+// generated scripts, where they can't span lines.
+fn parse_rename(s: &str) -> Result<(u32, String), String> {
+    match s.split_once('=') {
+        Some((_, label)) if label.chars().any(char::is_control) => Err(format!(
+            "labels can't contain control characters, got '{s}'"
+        )),
+        Some((val, label)) if !label.is_empty() => Ok((parse_value(val)?, label.to_string())),
+        _ => Err(format!("expected <VALUE>=<LABEL>, got '{s}'")),
+    }
+}
+
+// Parses a --filter expression, made of comma separated predicates (eg.
+// subsys:OVS,name~FLOW,value>=0x30002).
+fn parse_filter(s: &str) -> Result<Filter, String> {
+    s.split(',')
+        .map(|p| {
+            Ok(if let Some(subsys) = p.strip_prefix("subsys:") {
+                Predicate::Subsys(subsys.to_string())
+            } else if let Some(name) = p.strip_prefix("name~") {
+                Predicate::Name(name.to_string())
+            } else if let Some(cmp) = p.strip_prefix("value") {
+                // Two characters operators first, as they start with a single
+                // character one.
+                let (op, val): (fn(&u32, &u32) -> bool, _) =
+                    if let Some(val) = cmp.strip_prefix(">=") {
+                        (u32::ge, val)
+                    } else if let Some(val) = cmp.strip_prefix("<=") {
+                        (u32::le, val)
+                    } else if let Some(val) = cmp.strip_prefix('>') {
+                        (u32::gt, val)
+                    } else if let Some(val) = cmp.strip_prefix('<') {
+                        (u32::lt, val)
+                    } else if let Some(val) = cmp.strip_prefix('=') {
+                        (u32::eq, val)
+                    } else {
+                        return Err(format!("invalid value comparison '{p}'"));
+                    };
+                Predicate::Value(op, parse_value(val)?)
+            } else {
+                return Err(format!("invalid predicate '{p}'"));
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Filter)
+}
+
+// Parses a kernel version, eg. 6.8 or 6.8.0-31-generic, into its major and
+// minor numbers.
+fn parse_kernel_version(s: &str) -> Result<(u32, u32), String> {
+    let mut parts = s.split(['.', '-']);
+    match (
+        parts.next().and_then(|v| v.parse().ok()),
+        parts.next().and_then(|v| v.parse().ok()),
+    ) {
+        (Some(major), Some(minor)) => Ok((major, minor)),
+        _ => Err(format!("invalid kernel version '{s}'")),
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    init_logger(args.debug);
+
+    // Inspecting BTF does not require the drop reasons to be parsed (nor even
+    // supported).
+    if let Some(Commands::RawEnum(raw_enum_args)) = &args.command {
+        return raw_enum(&args, &raw_enum_args.name);
+    }
+    // The index is embedded, no BTF is needed.
+    #[cfg(feature = "history")]
+    if let Some(Commands::FirstSeen(first_seen_args)) = &args.command {
+        return first_seen(&first_seen_args.name);
+    }
+
+    // The schema does not depend on the drop reasons.
+    #[cfg(feature = "schema")]
+    if let Some(Commands::Dump(DumpArgs { schema: true, .. })) = &args.command {
+        println!("{}", format::json_schema()?);
+        return Ok(());
+    }
+    // The build rule runs drdump at build time, no BTF is needed now.
+    if let Some(Commands::Generate(generate_args)) = &args.command {
+        if let Some(system) = &generate_args.emit_build_rule {
+            return build_rule(&args, generate_args, system);
+        }
+    }
+
+    let patterns = (!args.btf_glob.is_empty()).then_some(args.btf_glob.as_slice());
+    let show_progress = args.progress && io::stderr().is_terminal();
+    let progress = |done, total| {
+        if show_progress {
+            eprint!("\rLoading BTF files: {done}/{total}");
+            if done == total {
+                eprintln!();
+            }
+        }
+    };
+    // Only the file name identifies a BTF file in a collection.
+    let prefer = args
+        .prefer_btf
+        .as_ref()
+        .and_then(|file| file.file_name())
+        .map(|name| name.to_string_lossy());
+    let printk = args.from_tracefs || args.from_printk_format.is_some();
+    let reasons = match (printk, patterns, prefer.as_deref()) {
+        (true, _, _) => from_tracefs(args.from_printk_format.as_deref()),
+        (false, _, _) if args.core_reasons_only => DropReasons::from_dir_core(&args.btf),
+        (false, _, Some(prefer)) => {
+            DropReasons::from_dir_preferring(&args.btf, patterns, prefer, progress)
+        }
+        (false, _, None) if show_progress => {
+            DropReasons::from_dir_with_progress(&args.btf, patterns, progress)
+        }
+        (false, patterns, None) => DropReasons::from_source(&source::Dir {
+            path: args.btf.clone(),
+            patterns: patterns.map(<[String]>::to_vec),
+        }),
+    };
+    let mut reasons = match reasons {
+        Ok(reasons) => reasons,
+        #[cfg(feature = "offline-db")]
+        Err(e) if args.offline => {
+            log::warn!("{e}. Using the drop reasons bundled in drdump, results reflect Linux {} and not the running kernel!", drdump::offline::KERNEL);
+            drdump::offline::reasons()
+        }
+        Err(e) => match printk {
+            false => match diagnose_btf_dir(&args.btf) {
+                Some(hint) => bail!("{e}\n\n{hint}"),
+                None => return Err(e),
+            },
+            true => return Err(e),
+        },
+    };
+    if args.strict && !reasons.out_of_range().is_empty() {
+        bail!(
+            "Values not fitting in 32 bits: {}",
+            reasons.out_of_range().join(", ")
+        );
+    }
+    reasons.apply_merge_strategy(match args.merge_strategy.as_str() {
+        "prefer-subsys" => MergeStrategy::PreferSubsys,
+        "error" => MergeStrategy::Error,
+        _ => MergeStrategy::KeepFirst,
+    })?;
+    if args.report_duplicates {
+        for (val, kept, dropped) in reasons.duplicates() {
+            eprintln!("{val}: kept={kept} dropped={dropped}");
+        }
+    }
+
+    let unknown_subsys = reasons.unknown_subsys();
+    if unknown_subsys {
+        log::info!("found more drop reasons than we know of. Drdump will still be able to resolve raw values into a sub-system when using resolve.");
+    }
+    if args.keep_mask && !reasons.keep_mask() {
+        log::warn!("no sub-system mask found, there is none to keep");
+    }
+
+    if let Some(overrides) = &args.overrides {
+        for (val, known, name) in reasons.merge(load_overrides(overrides, "overrides")?, args.force)
+        {
+            match args.force {
+                true => log::warn!("overriding reason {val} ({known}) with {name}"),
+                false => log::warn!("not overriding reason {val} ({known}) with {name}"),
+            }
+        }
+    }
+
+    let mut labels = match &args.rename_file {
+        Some(path) => load_overrides(path, "rename")?,
+        None => BTreeMap::new(),
+    };
+    if let Some((val, label)) = labels
+        .iter()
+        .find(|(_, label)| label.chars().any(char::is_control))
+    {
+        bail!("Invalid label {label:?} for reason {val}: labels can't contain control characters");
+    }
+    labels.extend(args.rename.iter().cloned());
+    for val in reasons.rename(labels) {
+        log::warn!("not renaming unknown reason {val}");
+    }
+    for id in reasons.rename_subsys(args.subsys_label.iter().cloned().collect()) {
+        log::warn!("not renaming unknown sub-system {id}");
+    }
+
+    let base = match args.base.as_str() {
+        "hex" => Base::Hex,
+        _ => Base::Dec,
+    };
+
+    // Handle the output. Depends on which operation was requested, dumping
+    // the drop reasons by default.
+    match &args.command {
+        Some(Commands::Dump(dump_args)) => dump(&args, dump_args, reasons, base)?,
+        None => dump(&args, &DumpArgs::parse_from(["dump"]), reasons, base)?,
+        Some(Commands::Resolve(resolve_args)) => {
+            // Bypass the merge of the enums, keeping the sub-systems.
+            if let Some(name) = &resolve_args.r#enum {
+                if printk {
+                    bail!("--enum requires the drop reasons to be read from BTF");
+                }
+                let (_, subsys) = reasons.into_parts();
+                reasons = DropReasons::new(drdump::enum_reasons(&args.btf, name)?, subsys);
+            }
+            let mut stdin = resolve_args.values.is_empty()
+                && resolve_args.bytes.is_empty()
+                && resolve_args.file.is_empty()
+                && resolve_args.all.is_none();
+            // Only the bare values are relative to the assumed sub-system,
+            // not the <SUBSYS>:<INDEX> queries.
+            let assumed = match &resolve_args.assume_subsys {
+                Some(subsys) => match reasons.subsys_id(subsys) {
+                    Some(id) => Some(id),
+                    None => bail!("Unknown sub-system {subsys}"),
+                },
+                None => None,
+            };
+            let assume = |val: u32| match assumed {
+                Some(id) if val >> 16 == 0 => val | id << 16,
+                _ => val,
+            };
+            let mut values = resolve_args
+                .values
+                .iter()
+                .map(|q| match q {
+                    Query::Raw(val) => Ok(assume(*val)),
+                    _ => q.value(&reasons),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            values.extend(resolve_args.bytes.iter().map(
+                |bytes| match resolve_args.endian.as_str() {
+                    "big" => assume(u32::from_be_bytes(*bytes)),
+                    _ => assume(u32::from_le_bytes(*bytes)),
+                },
+            ));
+            for file in resolve_args.file.iter() {
+                values.extend(read_values(file)?.into_iter().map(assume));
+            }
+            // Combined values are read from stdin as a file would be, as
+            // they can only be output once all are known.
+            if stdin && resolve_args.combine {
+                values.extend(read_values(Path::new("-"))?.into_iter().map(assume));
+                stdin = false;
+            }
+            if let Some(subsys) = &resolve_args.all {
+                let id = match reasons.subsys_id(subsys) {
+                    Some(id) => id,
+                    None => bail!("Unknown sub-system {subsys}"),
+                };
+                let last = match reasons.by_value().filter(|(v, _)| v >> 16 == id).last() {
+                    Some((last, _)) => last,
+                    None => bail!("No known drop reason in sub-system {subsys}"),
+                };
+                values.extend(id << 16..=last);
+            }
+            if resolve_args.only_unknown {
+                values.retain(|v| reasons.get(*v).is_none());
+            }
+            let mut unknown = Vec::new();
+            if resolve_args.group_unknown {
+                if resolve_args.format == "json" {
+                    bail!("--group-unknown is not supported with JSON");
+                }
+                (unknown, values) = values.into_iter().partition(|v| reasons.get(*v).is_none());
+            }
+
+            // Human readable resolutions display the labels of renamed reasons.
+            let labelled = reasons.labelled();
+            match (stdin, resolve_args.format.as_str()) {
+                (_, _) if resolve_args.json_stdin => resolve_json_stdin(&reasons)?,
+                (_, "json") if resolve_args.combine => {
+                    bail!("--combine is not supported with JSON")
+                }
+                (false, _) if resolve_args.combine => {
+                    println!("{}", combine(&labelled, &values, args.verbose > 0, base))
+                }
+                (true, _) if resolve_args.all_enums => {
+                    bail!("--all-enums does not apply to queries read from stdin")
+                }
+                (false, "json") if resolve_args.all_enums => {
+                    println!("{}", format::json_definitions(&labelled, &values)?)
+                }
+                (false, _) if resolve_args.all_enums => values
+                    .iter()
+                    .for_each(|v| println!("{}", definitions(&labelled, *v, base))),
+                (true, "json") => bail!("JSON is not supported when reading from stdin"),
+                (true, _) => interactive(
+                    &labelled,
+                    args.verbose > 0,
+                    resolve_args.only_unknown,
+                    resolve_args.group_unknown,
+                    base,
+                )?,
+                (false, "json") => {
+                    let resolved: Vec<_> = values.iter().map(|v| reasons.resolve(*v)).collect();
+                    println!("{}", format::json_resolved(&resolved)?);
+                }
+                (false, _) if resolve_args.show_hex => values.iter().for_each(|v| {
+                    println!(
+                        "{v} ({}) = {}",
+                        Base::Hex.format(*v),
+                        labelled.format_reason(*v, args.verbose > 0, base)
+                    )
+                }),
+                // Values are listed along their resolution, when resolving a
+                // whole sub-system.
+                (false, _) if resolve_args.all.is_some() => values.iter().for_each(|v| {
+                    println!(
+                        "{} = {}",
+                        base.format(*v),
+                        labelled.format_reason(*v, args.verbose > 0, base)
+                    )
+                }),
+                (false, _) => values.iter().for_each(|v| {
+                    println!("{}", labelled.format_reason(*v, args.verbose > 0, base))
+                }),
+            }
+            if !unknown.is_empty() {
+                println!("{}", unknown_summary(&labelled, &unknown));
+            }
+        }
+        Some(Commands::Decode(decode_args)) => {
+            let decode = |reasons: &DropReasons| -> Vec<_> {
+                decode_args
+                    .values
+                    .iter()
+                    .map(|v| reasons.decode(*v))
+                    .collect()
+            };
+            match (decode_args.format.as_str(), decode(&reasons).as_slice()) {
+                ("json", [decoded]) => println!("{}", serde_json::to_string_pretty(decoded)?),
+                ("json", decoded) => println!("{}", serde_json::to_string_pretty(decoded)?),
+                _ => println!(
+                    "{}",
+                    decode(&reasons.labelled())
+                        .iter()
+                        .map(format_decoded)
+                        .collect::<Vec<_>>()
+                        .join("\n\n")
+                ),
+            }
+        }
+        Some(Commands::ListSubsys(list_subsys_args)) => {
+            let name = match list_subsys_args.format.as_str() {
+                "json" => "subsys-count-json",
+                _ => "subsys-count",
+            };
+            let opts = format::Opts {
+                hide_empty: list_subsys_args.hide_empty,
+                ..Default::default()
+            };
+            println!("{}", render(name, &reasons, &opts)?);
+        }
+        Some(Commands::Categories(categories_args)) => {
+            filter(&categories_args.filters, &mut reasons)?;
+
+            let name = match categories_args.format.as_str() {
+                "json" => "categories-json",
+                _ => "categories",
+            };
+            let opts = format::Opts {
+                verbose: args.verbose > 0 || categories_args.group_equivalent,
+                group_equivalent: categories_args.group_equivalent,
+                ..Default::default()
+            };
+            println!("{}", render(name, &reasons, &opts)?);
+        }
+        Some(Commands::Diff(diff_args)) => {
+            filter(&diff_args.filters, &mut reasons)?;
+
+            let mut other = match (diff_args.running, &diff_args.baseline) {
+                (true, _) => DropReasons::from_dir(RUNNING_BTF)?,
+                (false, Some(baseline)) => load_baseline(baseline)?,
+                // Unwrap as the directory is required unless comparing with
+                // the running kernel or a baseline.
+                (false, None) => DropReasons::from_dir(diff_args.dir.as_ref().unwrap())?,
+            };
+            filter(&diff_args.filters, &mut other)?;
+
+            // Changes are reported from the running kernel or the baseline to
+            // the BTF files being validated, failing on mismatch.
+            let (old, new, reference) = match (diff_args.running, &diff_args.baseline) {
+                (true, _) => (&other, &reasons, Some("the running kernel")),
+                (false, Some(_)) => (&other, &reasons, Some("the baseline")),
+                (false, None) => (&reasons, &other, None),
+            };
+
+            if diff_args.intersection {
+                print_intersection(&diff_args.format, &intersection(old, new))?;
+            } else {
+                let diff = old.diff(new);
+                print_diff(&diff_args.format, &diff)?;
+                if let Some(reference) = reference.filter(|_| !diff.is_empty()) {
+                    bail!("Drop reasons do not match {reference}");
+                }
+            }
+        }
+        Some(Commands::ValidateHeader(validate_header_args)) => {
+            // Names printed by the kernel lack the prefixes of the header ones.
+            if args.from_tracefs || args.from_printk_format.is_some() {
+                bail!("validate-header requires the drop reasons to be read from BTF");
+            }
+
+            let path = &validate_header_args.header;
+            let header = fs::read_to_string(path)
+                .or_else(|e| bail!("Could not read {}: {e}", path.display()))?;
+            let header = parse_header(&header)
+                .or_else(|e| bail!("Could not parse {}: {e}", path.display()))?;
+            // The sub-system mask (see --keep-mask) is not a drop reason and
+            // is not reported by parse_header.
+            let btf: BTreeSet<_> = reasons
+                .by_value()
+                .filter(|(val, name)| val >> 16 == 0 && *name != "SKB_DROP_REASON_SUBSYS_MASK")
+                .map(|(_, name)| name)
+                .collect();
+
+            let header_only: Vec<_> = header
+                .iter()
+                .filter(|n| !btf.contains(n.as_str()))
+                .collect();
+            let btf_only: Vec<_> = btf.iter().filter(|n| !header.contains(**n)).collect();
+            header_only
+                .iter()
+                .for_each(|name| println!("header only: {name}"));
+            btf_only
+                .iter()
+                .for_each(|name| println!("BTF only: {name}"));
+            if !header_only.is_empty() || !btf_only.is_empty() {
+                bail!("Drop reasons do not match {}", path.display());
+            }
+        }
+        Some(Commands::Matrix(matrix_args)) => {
+            let dir = &matrix_args.dir;
+            let mut dirs = fs::read_dir(dir)
+                .or_else(|e| bail!("Could not read {}: {e}", dir.display()))?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?;
+            dirs.retain(|d| d.is_dir());
+            dirs.sort();
+
+            let kernels = dirs
+                .iter()
+                .map(|d| {
+                    let mut reasons = DropReasons::from_dir(d)?;
+                    filter(&matrix_args.filters, &mut reasons)?;
+                    // Unwrap as paths from read_dir always have a file name.
+                    Ok((
+                        d.file_name().unwrap().to_string_lossy().to_string(),
+                        reasons,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            println!(
+                "{}",
+                format::matrix(&kernels, matrix_args.format == "markdown")
+            );
+        }
+        Some(Commands::Generate(generate_args)) => generate(generate_args, reasons)?,
+        Some(Commands::Watch(watch_args)) => {
+            check_target_kernel(watch_args.target_kernel)?;
+            watch(&reasons.labelled(), args.verbose > 0, base)?;
+        }
+        Some(Commands::Explain(explain_args)) => {
+            let reason = &explain_args.reason;
+            let labelled = reasons.labelled();
+            match lookup(&reasons, reason).and_then(|val| format::explain(&labelled, val)) {
+                Some(explain) => println!("{explain}"),
+                None => bail!("Unknown drop reason {reason}"),
+            }
+        }
+        Some(Commands::Exists(exists_args)) => {
+            if lookup(&reasons, &exists_args.reason).is_none() {
+                process::exit(1);
+            }
+        }
+        Some(Commands::RawEnum(_)) => unreachable!(),
+        Some(Commands::SubsysReasons(subsys_reasons_args)) => {
+            for (val, name) in drdump::subsys_reasons(&args.btf, &subsys_reasons_args.name)? {
+                match reasons.get(val) {
+                    Some(kept) if kept != name => {
+                        println!("{} = {name} (hidden by {kept})", base.format(val))
+                    }
+                    _ => println!("{} = {name}", base.format(val)),
+                }
+            }
+        }
+        #[cfg(feature = "history")]
+        Some(Commands::FirstSeen(_)) => unreachable!(),
+        #[cfg(feature = "log-filter")]
+        Some(Commands::FilterLog(filter_log_args)) => filter_log(
+            &reasons.labelled(),
+            &filter_log_args.pattern,
+            filter_log_args.follow.as_deref(),
+        )?,
+    }
+
+    if unknown_subsys && args.fail_on_unknown_subsys {
+        bail!("The kernel defines more sub-systems than drdump knows of");
+    }
+
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+// Dump formats displayed to humans, noting when --limit hides some drop
+// reasons. The note would break the machine readable formats and generated
+// code.
+const HUMAN_FORMATS: &[&str] = &["raw", "raw-compact", "table", "tree"];
 
-    let btf =
-        BtfCollection::from_dir(args.btf).or_else(|e| bail!("Could not parse BTF files: {e}"))?;
+// Dumps the drop reasons.
+fn dump(args: &Args, dump_args: &DumpArgs, mut reasons: DropReasons, base: Base) -> Result<()> {
+    filter(&dump_args.filters, &mut reasons)?;
 
-    // First parse core drop reasons. If not found, the kernel doesn't support
-    // drop reasons.
-    let mut reasons = match parse_enum(&btf, "skb_drop_reason") {
-        Ok(Some(reasons)) => reasons,
-        Ok(None) => bail!("Drop reasons are not supported by this kernel"),
-        Err(e) => bail!(e),
-    };
+    if dump_args.dedup {
+        for (val, kept, name) in reasons.dedup() {
+            log::info!("collapsed duplicate reason {name} ({val}, kept {kept})");
+        }
+    }
+
+    let total = reasons.len();
+    // With counts, the limit applies to the most seen reasons instead.
+    if let (Some(limit), None) = (dump_args.limit, &dump_args.counts) {
+        reasons.truncate(limit);
+    }
+
+    if dump_args.fingerprint {
+        println!("{:016x}", reasons.fingerprint());
+        return Ok(());
+    }
+
+    if dump_args.compact_names {
+        // Use the sub-system labels, if renamed.
+        let labelled = reasons.labelled();
+        relabel(&mut reasons, |val, _| {
+            // Core reasons keep their name.
+            if val >> 16 == 0 {
+                return None;
+            }
+            let subsys = labelled.subsystem_for(val)?;
+            let subsys = subsys
+                .strip_prefix("SKB_DROP_REASON_SUBSYS_")
+                .unwrap_or(subsys);
+            Some(format!("{subsys}/{}", val & 0xffff))
+        });
+    }
 
-    // Special case the drop reason mask (SKB_DROP_REASON_SUBSYS_MASK).
-    reasons.remove(&0xffff0000);
+    if dump_args.strip_prefix {
+        relabel(&mut reasons, |_, name| {
+            name.strip_prefix("SKB_DROP_REASON_").map(str::to_string)
+        });
+    }
+
+    let opts = format::Opts {
+        verbose: args.verbose > 0,
+        deterministic: dump_args.deterministic,
+        stability: dump_args.stability,
+        by_subsys: dump_args.sort == "subsys",
+        align_names: dump_args.align_names,
+        ascii: dump_args.ascii,
+        // Only fit the output in the terminal when writing to it.
+        width: terminal_width().filter(|_| {
+            dump_args.output.is_none()
+                && dump_args.output_dir.is_none()
+                && io::stdout().is_terminal()
+        }),
+        name_fit: match (dump_args.truncate_names, dump_args.wrap_names) {
+            (true, _) => format::NameFit::Truncate,
+            (_, true) => format::NameFit::Wrap,
+            _ => format::NameFit::Overflow,
+        },
+        base,
+        ..Default::default()
+    };
+    if let Some(dir) = &dump_args.output_dir {
+        return write_formats(dir, &dump_args.formats, &reasons, &opts);
+    }
 
-    // Parse non-core drop reasons.
-    for r#enum in NON_CORE_DROP_REASONS {
-        if let Some(mut subsys_reasons) = parse_enum(&btf, r#enum)? {
-            while let Some((val, reason)) = subsys_reasons.pop_first() {
-                // Do not overwrite known values. Some sub-system do this for
-                // reusing some of the very generic core reasons. Eg.
-                // SKB_CONSUMED.
-                reasons.entry(val).or_insert(reason);
+    let output = match (&dump_args.counts, dump_args.format.as_str()) {
+        (Some(path), format) => {
+            let counts = load_counts(path)?;
+            let mut output = with_counts(&reasons, &counts, format, dump_args.limit, &opts)?;
+            output.push('\n');
+            output.into_bytes()
+        }
+        #[cfg(feature = "msgpack")]
+        (None, "msgpack") => format::msgpack(&reasons)?,
+        #[cfg(feature = "cbor")]
+        (None, "cbor") => format::cbor(&reasons)?,
+        (None, name) => {
+            let mut output = render(name, &reasons, &opts)?;
+            output.push('\n');
+            if HUMAN_FORMATS.contains(&name) && reasons.len() < total {
+                output.push_str(&format!("... ({} of {total} shown)\n", reasons.len()));
             }
+            output.into_bytes()
+        }
+    };
+
+    match &dump_args.output {
+        Some(path) => {
+            fs::write(path, output).or_else(|e| bail!("Could not write {}: {e}", path.display()))?
         }
+        None => io::stdout().write_all(&output)?,
+    }
+
+    Ok(())
+}
+
+// Labels the drop reasons for which f returns a label, given their value and
+// kernel name. The labels set by --rename are kept.
+fn relabel<F: Fn(u32, &str) -> Option<String>>(reasons: &mut DropReasons, f: F) {
+    let labels = reasons
+        .by_value()
+        .filter(|(val, _)| reasons.label(*val).is_none())
+        .filter_map(|(val, name)| Some((val, f(val, name)?)))
+        .collect();
+    reasons.rename(labels);
+}
+
+// Returns the number of columns of the terminal stdout is writing to, if any.
+// $COLUMNS takes precedence over the size of the terminal, eg. to fit the
+// output in a given width.
+fn terminal_width() -> Option<usize> {
+    match std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        Some(columns) => Some(columns),
+        None => terminal_size::terminal_size_of(io::stdout())
+            .map(|(terminal_size::Width(width), _)| width as usize),
+    }
+}
+
+// Logger printing the diagnostics on stderr, prefixed by their level.
+struct Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
     }
 
-    // Get a list of all the known subsystems that can register non-core drop
-    // reasons. This might return more elements than the ones we know of (if we
-    // haven't added support for those yet).
-    let subsys = parse_enum(&btf, "skb_drop_reason_subsys")?;
-    if let Some(ref subsys) = subsys {
-        if subsys.len() > SKB_DROP_REASON_SUBSYS_NUM {
-            eprint!("INFO: found more drop reasons than we know of. Drdump will still be able to resolve raw values into a sub-system when using --resolve.\n\n");
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}: {}", record.level(), record.args());
         }
     }
 
-    // Handle the output. Depends on which operation was requested.
-    if let Some(resolve) = args.resolve {
-        println!(
-            "{}",
-            format_reason(resolve, &reasons, subsys.as_ref(), args.verbose)
-        );
-    } else {
-        match args.format.as_str() {
-            "raw" => {
-                let width = (reasons
-                    .keys()
-                    .max()
-                    .unwrap_or(&0)
-                    .checked_ilog10()
-                    .unwrap_or(0)
-                    + 1) as usize;
-                reasons.keys().for_each(|i| {
-                    println!(
-                        "{i:width$} = {}",
-                        format_reason(*i, &reasons, subsys.as_ref(), args.verbose)
-                    )
-                });
-            }
-            "bpftrace" => println!("{}", format_bpftrace(&reasons)),
-            "stap" => println!("{}", format_stap(&reasons)),
-            _ => (),
+    fn flush(&self) {}
+}
+
+// Installs the logger, printing the warnings and informational messages by
+// default and more detailed diagnostics with each -d.
+fn init_logger(debug: u8) {
+    static LOGGER: Logger = Logger;
+
+    // The logger can't already be set, as this is only called once.
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(match debug {
+            0 => log::LevelFilter::Info,
+            1 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        });
+    }
+}
+
+// Reads the drop reasons from the given kfree_skb tracepoint format file, or
+// from the first one found in tracefs.
+fn from_tracefs(format: Option<&Path>) -> Result<DropReasons> {
+    let format = match format {
+        Some(format) => format,
+        None => match TRACEFS_KFREE_SKB.iter().find(|f| Path::new(f).exists()) {
+            Some(format) => Path::new(format),
+            None => bail!("Could not find the kfree_skb tracepoint format, is tracefs mounted?"),
+        },
+    };
+
+    let reasons = DropReasons::from_tracefs(format)?;
+    log::info!("drop reasons were read from {} and not BTF. Only core reasons are known and their names lack the SKB_DROP_REASON_ prefix.", format.display());
+    Ok(reasons)
+}
+
+// Looks for the common reasons why BTF files can't be read from dir, returning
+// a message explaining how to fix it.
+fn diagnose_btf_dir(dir: &Path) -> Option<String> {
+    let dir_name = dir.display();
+    let permission = format!("Permission denied reading {dir_name}, try running drdump as root.");
+    let alternatives =
+        "Otherwise use --btf to point to another directory holding BTF files, or --from-tracefs.";
+
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let hint = match dir == Path::new(RUNNING_BTF) {
+                true => " Is sysfs mounted (eg. in containers, mount -t sysfs sysfs /sys) and the kernel built with CONFIG_DEBUG_INFO_BTF?",
+                false => "",
+            };
+            return Some(format!("{dir_name} does not exist.{hint} {alternatives}"));
+        }
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            return Some(format!("{permission} {alternatives}"))
+        }
+        Err(_) => return None,
+    };
+    if entries.next().is_none() {
+        return Some(format!("{dir_name} is empty. {alternatives}"));
+    }
+
+    match fs::File::open(dir.join("vmlinux")) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(format!(
+            "No vmlinux BTF file found in {dir_name}. {alternatives}"
+        )),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Some(format!("{permission} {alternatives}"))
         }
+        _ => None,
+    }
+}
+
+// Writes the drop reasons in the given formats to dir/drop_reasons.<ext>.
+fn write_formats(
+    dir: &Path,
+    formats: &[String],
+    reasons: &DropReasons,
+    opts: &format::Opts,
+) -> Result<()> {
+    let registry = format::Registry::with_builtins();
+    fs::create_dir_all(dir).or_else(|e| bail!("Could not create {}: {e}", dir.display()))?;
+
+    for name in formats.iter() {
+        let format = match registry.get(name) {
+            Some(format) => format,
+            None => bail!("Unknown format {name}"),
+        };
+
+        let path = dir.join(format!("drop_reasons.{}", format.extension()));
+        fs::write(&path, format.render(reasons, opts)? + "\n")
+            .or_else(|e| bail!("Could not write {}: {e}", path.display()))?;
     }
 
     Ok(())
 }
 
-// Formats a reason for pretty printing. If verbose is set, prints the subsystem
-// enum variant corresponding to a reason. If a reason is not known, try to
-// always print its subsystem if we have a match.
-fn format_reason(
-    val: u32,
-    reasons: &BTreeMap<u32, String>,
-    subsys: Option<&BTreeMap<u32, String>>,
-    verbose: bool,
-) -> String {
-    let format = |s: &str, verbose: bool| -> String {
-        if verbose && subsys.is_some() {
-            let subsys_id = val >> 16;
+// Renders the drop reasons using one of the registered output formats.
+fn render(name: &str, reasons: &DropReasons, opts: &format::Opts) -> Result<String> {
+    match format::Registry::with_builtins().get(name) {
+        Some(format) => format.render(reasons, opts),
+        None => bail!("Unknown format {name}"),
+    }
+}
+
+// Generates a monitoring script or code embedding the drop reasons.
+fn generate(generate_args: &GenerateArgs, mut reasons: DropReasons) -> Result<()> {
+    if ["bpftrace", "stap"].contains(&generate_args.lang.as_str()) {
+        check_target_kernel(generate_args.target_kernel)?;
+    }
+
+    filter(&generate_args.filters, &mut reasons)?;
+
+    if generate_args.dedup {
+        for (val, kept, name) in reasons.dedup() {
+            log::info!("collapsed duplicate reason {name} ({val}, kept {kept})");
+        }
+    }
+
+    // Only the C enum uses the names as identifiers, other generated code
+    // embeds them as strings.
+    if generate_args.lang == "c-enum"
+        && (generate_args.validate_names || generate_args.sanitize_names)
+    {
+        validate_names(&mut reasons, generate_args.sanitize_names)?;
+    }
+    // bpftrace strings have a fixed size and are silently truncated.
+    if generate_args.lang == "bpftrace" {
+        // The script embeds the labels of renamed reasons.
+        check_name_lengths(
+            &reasons.labelled(),
+            generate_args.name_buf,
+            generate_args.truncate,
+        )?;
+    }
 
-            // Unwrap as we just made sure it's Some<>.
-            if let Some(name) = subsys.unwrap().get(&subsys_id) {
-                return format!("{s} (sub-system: {name})");
+    let kprobe = generate_args.probe == "kprobe";
+    if kprobe {
+        if !generate_args.tracepoint.is_empty() {
+            bail!("--tracepoint can't be used with --probe kprobe");
+        }
+        if ["bpftrace", "stap"].contains(&generate_args.lang.as_str()) {
+            log::warn!("the script probes kernel functions, whose availability depends on the kernel (eg. they can be inlined or renamed)");
+        }
+    }
+
+    let name = match generate_args.lang.as_str() {
+        "stap" if generate_args.stap_embedded => {
+            if !generate_args.tracepoint.is_empty() {
+                bail!("--tracepoint can't be used with --stap-embedded");
             }
+            log::warn!("the script uses embedded C and requires guru mode (stap -g)");
+            "stap-embedded"
         }
-        s.to_string()
+        "stap" if generate_args.stap_legacy || kprobe => "stap-legacy",
+        "bpftrace" if kprobe && generate_args.bpftrace_style == "switch" => {
+            bail!("--bpftrace-style switch is not supported with --probe kprobe")
+        }
+        "bpftrace" if kprobe => "bpftrace-kprobe",
+        "bpftrace-json" if kprobe => "bpftrace-kprobe-json",
+        "bpftrace" if generate_args.bpftrace_style == "switch" => "bpftrace-switch",
+        lang => lang,
+    };
+    let opts = format::Opts {
+        min_count: generate_args.min_count,
+        tracepoints: generate_args.tracepoint.clone(),
+        wrap_width: generate_args.wrap_width,
+        duration: generate_args.duration,
+        annotate: generate_args.annotate_script,
+        ..Default::default()
     };
+    println!("{}", render(name, &reasons, &opts)?);
+
+    Ok(())
+}
+
+// Outputs a cmake or meson build rule regenerating the c-enum header as
+// drop_reasons.h, running drdump with the options affecting the header. The
+// rule depends on the vmlinux BTF file, for the header to follow the kernel.
+fn build_rule(args: &Args, generate_args: &GenerateArgs, system: &str) -> Result<()> {
+    if generate_args.lang != "c-enum" {
+        bail!("--emit-build-rule only applies to c-enum");
+    }
+    if args.from_tracefs || args.from_printk_format.is_some() {
+        bail!("--emit-build-rule requires the drop reasons to be read from BTF");
+    }
+
+    let btf = args.btf.to_string_lossy();
+    let vmlinux = args.btf.join("vmlinux");
+    let vmlinux = vmlinux.to_string_lossy();
+    let mut command = vec!["--btf", &btf, "generate", "c-enum"];
+    for (set, flag) in [
+        (generate_args.validate_names, "--validate-names"),
+        (generate_args.sanitize_names, "--sanitize-names"),
+        (generate_args.dedup, "--dedup"),
+    ] {
+        if set {
+            command.push(flag);
+        }
+    }
+
+    match system {
+        "cmake" => {
+            // Arguments are quoted, the output redirection can't be.
+            let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+            println!(
+                "find_program(DRDUMP drdump REQUIRED)
+add_custom_command(
+  OUTPUT drop_reasons.h
+  COMMAND ${{DRDUMP}} {} > drop_reasons.h
+  DEPENDS {}
+  COMMENT \"Generating drop_reasons.h using drdump\"
+)
+add_custom_target(drop_reasons_h ALL DEPENDS drop_reasons.h)",
+                command
+                    .iter()
+                    .map(|arg| quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                quote(&vmlinux)
+            );
+        }
+        _ => {
+            let quote = |s: &str| format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"));
+            println!(
+                "drop_reasons_h = custom_target(
+  'drop_reasons.h',
+  output: 'drop_reasons.h',
+  command: [find_program('drdump'), {}],
+  capture: true,
+  depend_files: {},
+  build_by_default: true,
+)",
+                command
+                    .iter()
+                    .map(|arg| quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                quote(&vmlinux)
+            );
+        }
+    }
+
+    Ok(())
+}
 
-    match reasons.get(&val) {
-        Some(name) => format(name, verbose),
-        None => format(&format!("Unknown reason {val}"), true),
+// Checks the monitoring scripts can run on the target kernel, if any.
+fn check_target_kernel(target: Option<(u32, u32)>) -> Result<()> {
+    let (major, minor) = format::SCRIPT_MIN_KERNEL;
+    match target {
+        Some(target) if target < (major, minor) => bail!(
+            "Monitoring scripts require Linux >= {major}.{minor}, the target kernel {}.{} is not supported",
+            target.0,
+            target.1
+        ),
+        _ => Ok(()),
     }
 }
 
-// Parses a kernel enum into an ordered BTreeMap of (val <> name).
-fn parse_enum(btf: &BtfCollection, name: &str) -> Result<Option<BTreeMap<u32, String>>> {
-    let mut values = BTreeMap::new();
+// Applies the listing filters requested on the command line.
+fn filter(filters: &Filters, reasons: &mut DropReasons) -> Result<()> {
+    let (since, until) = (
+        filters.since_value.unwrap_or(u32::MIN),
+        filters.until_value.unwrap_or(u32::MAX),
+    );
+    if since > until {
+        bail!("--since-value ({since}) is greater than --until-value ({until})");
+    }
+    reasons.retain_range(since..=until);
 
-    let types = match btf.resolve_types_by_name(name) {
-        Ok(types) => types,
-        Err(_) => return Ok(None),
-    };
+    let (min, max) = (
+        filters.min_subsys_id.unwrap_or(u32::MIN),
+        filters.max_subsys_id.unwrap_or(u32::MAX),
+    );
+    if min > max {
+        bail!("--min-subsys-id ({min}) is greater than --max-subsys-id ({max})");
+    }
+    reasons.retain(|val, _| (min..=max).contains(&(val >> 16)));
+
+    if let Some(id) = filters.subsys_id {
+        reasons.retain(|val, _| val >> 16 == id || (filters.with_core && val >> 16 == 0));
+    }
+
+    if let Some(Filter(predicates)) = &filters.filter {
+        // Resolve the sub-system names first, as the reasons can't be looked
+        // up while being filtered.
+        let subsys = predicates
+            .iter()
+            .map(|p| match p {
+                Predicate::Subsys(name) => match reasons.subsys_id(name) {
+                    Some(id) => Ok(Some(id)),
+                    None => bail!("Unknown sub-system {name}"),
+                },
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        reasons.retain(|val, name| {
+            predicates.iter().zip(subsys.iter()).all(|p| match p {
+                (Predicate::Subsys(_), Some(id)) => val >> 16 == *id,
+                (Predicate::Name(substr), _) => name.contains(substr.as_str()),
+                (Predicate::Value(op, other), _) => op(&val, other),
+                _ => false,
+            })
+        });
+    }
+
+    Ok(())
+}
 
-    let (btf, r#enum) = match types.iter().find(|(_, t)| matches!(t, &Type::Enum(_))) {
-        Some((btf, Type::Enum(r#enum))) => (btf, r#enum),
-        _ => return Ok(None),
+// Checks all drop reason names are valid C identifiers. If `sanitize` is set,
+// invalid names are fixed instead of failing: invalid characters are replaced
+// with underscores and names starting with a digit are prefixed with one.
+fn validate_names(reasons: &mut DropReasons, sanitize: bool) -> Result<()> {
+    let is_ident = |name: &str| {
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
     };
 
-    for member in r#enum.members.iter() {
-        let val = member.val() as u32;
-        values.insert(val, btf.resolve_name(member)?);
+    let mut sanitized = BTreeMap::new();
+    for (val, name) in reasons.by_value().filter(|(_, name)| !is_ident(name)) {
+        if !sanitize {
+            bail!("Drop reason {val} name '{name}' is not a valid identifier");
+        }
+
+        let mut fixed: String = name
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        if !fixed.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            fixed.insert(0, '_');
+        }
+
+        log::warn!("renaming reason {val} ({name}) to {fixed}");
+        sanitized.insert(val, fixed);
     }
 
-    Ok(Some(values))
+    reasons.merge(sanitized, true);
+    Ok(())
+}
+
+// Checks the drop reason names fit in a buffer of the given size, including
+// their NUL terminator. Only warns if truncate is set.
+fn check_name_lengths(reasons: &DropReasons, size: usize, truncate: bool) -> Result<()> {
+    let long: Vec<_> = reasons
+        .by_value()
+        .map(|(_, name)| name)
+        .filter(|name| name.len() >= size)
+        .collect();
+    let longest = match long.iter().map(|name| name.len()).max() {
+        Some(longest) => longest,
+        None => return Ok(()),
+    };
+
+    let msg = format!(
+        "{} drop reason name(s) do not fit in {size} bytes including the NUL terminator (longest is {longest} bytes): {}",
+        long.len(),
+        long.join(", ")
+    );
+    match truncate {
+        true => {
+            log::warn!("{msg}, they will be truncated");
+            Ok(())
+        }
+        false => bail!("{msg}. Use a larger --name-buf or --truncate"),
+    }
 }
 
-// Construct a bpftrace script to monitor drop reasons.
-fn format_bpftrace(reasons: &BTreeMap<u32, String>) -> String {
-    let reasons_def = reasons.iter().fold(String::new(), |mut out, (val, name)| {
-        write!(out, "    @drop_reasons[{val}] = \"{name}\";\n").unwrap();
-        out
+// Formats the removed and renamed reasons as compiler-style warnings, for CI
+// annotation systems. BTF has no line numbers, the reason name and value are
+// used instead.
+fn diff_checkpatch(diff: &ReasonDiff) -> String {
+    let mut out = String::new();
+    diff.removed.iter().for_each(|r| {
+        out.push_str(&format!(
+            "{}:{}: warning: drop reason removed, this can break userspace\n",
+            r.name, r.value
+        ))
     });
+    diff.renamed.iter().for_each(|r| {
+        out.push_str(&format!(
+            "{}:{}: warning: drop reason renamed to {}, this can break userspace\n",
+            r.from, r.value, r.to
+        ))
+    });
+    out
+}
+
+// Formats the changes as CSV, with a header row.
+fn diff_csv(diff: &ReasonDiff) -> String {
+    let row = |status: &str, val: u32, old: &str, new: &str| {
+        format!(
+            "{status},{val},{},{}\n",
+            format::csv_escape(old),
+            format::csv_escape(new)
+        )
+    };
+
+    let mut out = "status,value,name_old,name_new\n".to_string();
+    diff.added
+        .iter()
+        .for_each(|r| out.push_str(&row("added", r.value, "", &r.name)));
+    diff.removed
+        .iter()
+        .for_each(|r| out.push_str(&row("removed", r.value, &r.name, "")));
+    diff.renamed
+        .iter()
+        .for_each(|r| out.push_str(&row("renamed", r.value, &r.from, &r.to)));
+    out
+}
+
+// Parses the member names of enum skb_drop_reason from a C header, eg.
+// include/net/dropreason-core.h. The sub-system mask is not reported as it is
+// not a real reason.
+fn parse_header(header: &str) -> Result<BTreeSet<String>> {
+    let body = match header
+        .split_once("enum skb_drop_reason {")
+        .and_then(|(_, body)| body.split_once("};"))
+    {
+        Some((body, _)) => body,
+        None => bail!("enum skb_drop_reason not found"),
+    };
+
+    // Drop the comments and preprocessor directives.
+    let mut code = String::new();
+    let mut rest = body;
+    while let Some((before, after)) = rest.split_once("/*") {
+        code.push_str(before);
+        rest = after
+            .split_once("*/")
+            .map(|(_, after)| after)
+            .unwrap_or_default();
+    }
+    code.push_str(rest);
+    let code: String = code
+        .lines()
+        .map(|line| line.split_once("//").map(|(l, _)| l).unwrap_or(line))
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let names: BTreeSet<_> = code
+        .split(',')
+        .filter_map(|member| member.split('=').next())
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && *name != "SKB_DROP_REASON_SUBSYS_MASK")
+        .map(str::to_string)
+        .collect();
+    if names.is_empty() {
+        bail!("enum skb_drop_reason has no member");
+    }
+    Ok(names)
+}
 
+// Formats the breakdown of a raw value, one part per line.
+fn format_decoded(decoded: &Decoded) -> String {
+    let unknown = "unknown".to_string();
     format!(
-        "#!/usr/bin/bpftrace
-
-BEGIN
-{{
-    printf(\"Tracing dropped skbs... Hit Ctrl-C to end.\\n\");
-}}
-
-tracepoint:skb:kfree_skb
-{{
-{reasons_def}
-    @stack[ksym(args->location),@drop_reasons[args->reason]] = count();
-    clear(@drop_reasons);
-}}
-
-interval:s:5
-{{
-    time(\"%F %T %z (%Z)\\n\");
-    print(@stack);
-    printf(\"\\n\");
-    clear(@stack);
-}}
-
-END
-{{
-  clear(@stack);
-}}"
+        "Value:      {} ({})\nSub-system: {} ({})\nIndex:      {}\nReason:     {}",
+        decoded.value,
+        Base::Hex.format(decoded.value),
+        decoded.subsystem_id,
+        decoded.subsystem.as_ref().unwrap_or(&unknown),
+        decoded.index,
+        decoded.reason.as_ref().unwrap_or(&unknown),
     )
 }
 
-// Construct a stap script to monitor drop reasons.
-fn format_stap(reasons: &BTreeMap<u32, String>) -> String {
-    let reasons_def = reasons.iter().fold(String::new(), |mut out, (val, name)| {
-        write!(out, "    drop_reasons[{val}] = \"{name}\";\n").unwrap();
-        out
+// Prints a diff in the requested format.
+fn print_diff(format: &str, diff: &ReasonDiff) -> Result<()> {
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(diff)?),
+        "csv" => print!("{}", diff_csv(diff)),
+        "checkpatch" => print!("{}", diff_checkpatch(diff)),
+        _ => print!("{diff}"),
+    }
+    Ok(())
+}
+
+// Returns the drop reasons defined with the same value and name in both sets.
+fn intersection(a: &DropReasons, b: &DropReasons) -> Vec<DropReason> {
+    a.by_value()
+        .filter(|(val, name)| b.get(*val) == Some(*name))
+        .filter_map(|(val, _)| a.reason(val))
+        .collect()
+}
+
+// Prints the drop reasons common to two sets in the requested format.
+fn print_intersection(format: &str, reasons: &[DropReason]) -> Result<()> {
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(reasons)?),
+        "checkpatch" => bail!("The checkpatch format is not supported with --intersection"),
+        "csv" => {
+            println!("value,name");
+            reasons
+                .iter()
+                .for_each(|r| println!("{},{}", r.value, format::csv_escape(&r.name)));
+        }
+        _ => reasons
+            .iter()
+            .for_each(|r| println!("{} = {}", r.value, r.name)),
+    }
+    Ok(())
+}
+
+// Prints the kernel version a drop reason first appeared in, if it is part of
+// the embedded index.
+#[cfg(feature = "history")]
+fn first_seen(name: &str) -> Result<()> {
+    let prefixed = format!("SKB_DROP_REASON_{name}");
+    match drdump::history::first_seen(name).or_else(|| drdump::history::first_seen(&prefixed)) {
+        Some(version) => println!("{version}"),
+        None => bail!("{name} is not in the first-seen index, the kernel version it first appeared in is not known"),
+    }
+    Ok(())
+}
+
+// Dumps the members of a kernel enum exactly as found in BTF, per BTF file
+// defining it.
+fn raw_enum(args: &Args, name: &str) -> Result<()> {
+    let base = match args.base.as_str() {
+        "hex" => Base::Hex,
+        _ => Base::Dec,
+    };
+
+    let members = match drdump::raw_enum(&args.btf, name) {
+        Ok(members) => members,
+        Err(e) => match diagnose_btf_dir(&args.btf) {
+            Some(hint) => bail!("{e}\n\n{hint}"),
+            None => return Err(e),
+        },
+    };
+    let multiple = members.iter().any(|(file, _, _)| *file != members[0].0);
+    let mut last = None;
+    for (file, val, member) in members.iter() {
+        if multiple && last != Some(file) {
+            println!("{file}:");
+            last = Some(file);
+        }
+        println!("{} = {member}", val.format(base));
+    }
+    Ok(())
+}
+
+// Reads queries from stdin until EOF and prints their resolution. Queries are
+// either raw values (in decimal or hexadecimal), resolved into a reason, or
+// reason names prefixed with "name:" (with or without their SKB_DROP_REASON_
+// prefix), resolved into a raw value.
+fn interactive(
+    reasons: &DropReasons,
+    verbose: bool,
+    only_unknown: bool,
+    group_unknown: bool,
+    base: Base,
+) -> Result<()> {
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+    let mut unknown = Vec::new();
+
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        let query = line.trim();
+        if query.is_empty() {
+            continue;
+        }
+
+        match query.strip_prefix("name:") {
+            Some(name) => match lookup(reasons, name.trim()) {
+                Some(val) => println!("{}", base.format(val)),
+                None => println!("Unknown reason name {}", name.trim()),
+            },
+            None => match parse_value(query) {
+                Ok(val) if only_unknown && reasons.get(val).is_some() => (),
+                Ok(val) if group_unknown && reasons.get(val).is_none() => unknown.push(val),
+                Ok(val) => println!("{}", reasons.format_reason(val, verbose, base)),
+                Err(_) => eprintln!("Invalid query '{query}' (expected <VALUE> or name:<NAME>)"),
+            },
+        }
+    }
+
+    if prompt {
+        println!();
+    }
+    if !unknown.is_empty() {
+        println!("{}", unknown_summary(reasons, &unknown));
+    }
+    Ok(())
+}
+
+// Resolves the raw values of a JSON array read from stdin, outputting their
+// resolutions as a JSON array in the same order. Elements which are not raw
+// values (eg. strings or negative numbers) resolve to null.
+fn resolve_json_stdin(reasons: &DropReasons) -> Result<()> {
+    let values: Vec<serde_json::Value> = serde_json::from_reader(io::stdin())
+        .or_else(|e| bail!("Could not parse a JSON array of values from stdin: {e}"))?;
+
+    let resolved: Vec<_> = values
+        .iter()
+        .map(|val| {
+            val.as_u64()
+                .and_then(|val| u32::try_from(val).ok())
+                .map(|val| reasons.resolve(val))
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&resolved)?);
+    Ok(())
+}
+
+// Reads raw values from a file (stdin if "-"), one per line. Empty lines are
+// ignored.
+fn read_values(path: &Path) -> Result<Vec<u32>> {
+    let content = match path.to_str() {
+        Some("-") => io::read_to_string(io::stdin())?,
+        _ => {
+            fs::read_to_string(path).or_else(|e| bail!("Could not read {}: {e}", path.display()))?
+        }
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            parse_value(line.trim()).or_else(|e| bail!("{}:{}: {e}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+// Resolves each distinct value once, prefixed by the number of times it was
+// given, most frequent first, eg. "     12 2 = SKB_DROP_REASON_NOT_SPECIFIED".
+fn combine(reasons: &DropReasons, values: &[u32], verbose: bool, base: Base) -> String {
+    let mut count: BTreeMap<u32, usize> = BTreeMap::new();
+    values
+        .iter()
+        .for_each(|val| *count.entry(*val).or_default() += 1);
+
+    let mut count: Vec<_> = count.into_iter().collect();
+    // Stable, values with the same count stay ordered.
+    count.sort_by(|(_, a), (_, b)| b.cmp(a));
+    count
+        .iter()
+        .map(|(val, n)| {
+            format!(
+                "{n:>7} {} = {}",
+                base.format(*val),
+                reasons.format_reason(*val, verbose, base)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Lists all the definitions of a raw value, eg.
+// "2 = skb_drop_reason::SKB_DROP_REASON_NOT_SPECIFIED, ovs_drop_reason::OVS_DROP_REASON_X".
+fn definitions(reasons: &DropReasons, val: u32, base: Base) -> String {
+    let definitions = reasons.definitions(val);
+    match definitions.is_empty() {
+        true => format!(
+            "{} = {}",
+            base.format(val),
+            reasons.format_reason(val, false, base)
+        ),
+        false => format!(
+            "{} = {}",
+            base.format(val),
+            definitions
+                .iter()
+                .map(|(r#enum, name)| format!("{enum}::{name}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+// Summarizes values not matching a known drop reason, counting them by
+// sub-system, eg. "3 values unknown (subsystems: <none> x1, OPENVSWITCH x2)".
+fn unknown_summary(reasons: &DropReasons, unknown: &[u32]) -> String {
+    let mut count: BTreeMap<&str, usize> = BTreeMap::new();
+    unknown.iter().for_each(|val| {
+        let subsys = match reasons.subsystem_for(*val) {
+            Some(subsys) => subsys
+                .strip_prefix("SKB_DROP_REASON_SUBSYS_")
+                .unwrap_or(subsys),
+            None => "<none>",
+        };
+        *count.entry(subsys).or_default() += 1;
     });
 
-    format!("#! /usr/bin/env stap
-
-global skb_drop_reason
-global drop_reasons
-
-probe kernel.trace(\"kfree_skb\") {{
-    skb_drop_reason[$location, $reason] <<< 1;
-}}
-
-probe begin {{
-    printf(\"Tracing dropped skbs... Hit Ctrl-C to end.\\n\");
-}}
-
-# Report every 5 seconds
-probe timer.sec(5)
-{{
-    printf(\"\\n%s\", tz_ctime(gettimeofday_s()))
-{reasons_def}
-    printf(\"\\n%-35s%-35s%10s\\n\",\"Drop\",\"Location\",\"Count\");
-    foreach([location, reason] in skb_drop_reason) {{
-        printf(\"%-35s%-35s%10d\\n\",symname(location),drop_reasons[reason],@count(skb_drop_reason[location, reason]))
-    }}
-    delete skb_drop_reason
-}}")
+    format!(
+        "{} value{} unknown (subsystems: {})",
+        unknown.len(),
+        if unknown.len() > 1 { "s" } else { "" },
+        count
+            .iter()
+            .map(|(subsys, count)| format!("{subsys} x{count}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// Loads a JSON file mapping raw values to drop reason names, eg.
+// {"65536": "MY_SUBSYS_DROP_FOO"}.
+// The file is described as `what` in errors (eg. "overrides").
+fn load_overrides(path: &Path, what: &str) -> Result<BTreeMap<u32, String>> {
+    let file = fs::read_to_string(path)
+        .or_else(|e| bail!("Could not read {what} file {}: {e}", path.display()))?;
+    let overrides: BTreeMap<String, String> = serde_json::from_str(&file)
+        .or_else(|e| bail!("Could not parse {what} file {}: {e}", path.display()))?;
+
+    overrides
+        .into_iter()
+        .map(|(val, name)| match val.parse::<u32>() {
+            Ok(val) => Ok((val, name)),
+            Err(e) => bail!("Invalid raw value '{val}' in {what}: {e}"),
+        })
+        .collect()
+}
+
+// Loads observed counts from a JSON file mapping raw values to counts. For the
+// same reason as overrides, the values are read as strings.
+fn load_counts(path: &Path) -> Result<BTreeMap<u32, u64>> {
+    let file = fs::read_to_string(path)
+        .or_else(|e| bail!("Could not read counts file {}: {e}", path.display()))?;
+    let counts: BTreeMap<String, u64> = serde_json::from_str(&file)
+        .or_else(|e| bail!("Could not parse counts file {}: {e}", path.display()))?;
+
+    counts
+        .into_iter()
+        .map(|(val, count)| match val.parse::<u32>() {
+            Ok(val) => Ok((val, count)),
+            Err(e) => bail!("Invalid raw value '{val}' in counts: {e}"),
+        })
+        .collect()
+}
+
+// Formats the drop reasons annotated with their count, sorted by descending
+// count, either as raw lines (eg. "     12 2 = SKB_DROP_REASON_NOT_SPECIFIED")
+// or as the JSON schema with an added count field. Only the first `limit`
+// reasons are kept, if set.
+fn with_counts(
+    reasons: &DropReasons,
+    counts: &BTreeMap<u32, u64>,
+    format: &str,
+    limit: Option<usize>,
+    opts: &format::Opts,
+) -> Result<String> {
+    #[derive(Serialize)]
+    struct Counted {
+        count: u64,
+        #[serde(flatten)]
+        reason: DropReason,
+    }
+
+    counts
+        .keys()
+        .filter(|val| reasons.reason(**val).is_none())
+        .for_each(|val| log::debug!("counted value {val} is not a known drop reason"));
+
+    let mut counted: Vec<_> = reasons
+        .iter()
+        .map(|reason| Counted {
+            count: counts.get(&reason.value).copied().unwrap_or_default(),
+            reason,
+        })
+        .collect();
+    // Stable, reasons with the same count stay ordered.
+    counted.sort_by_key(|c| std::cmp::Reverse(c.count));
+    if let Some(limit) = limit {
+        counted.truncate(limit);
+    }
+
+    Ok(match format {
+        "raw" => {
+            let labelled = reasons.labelled();
+            counted
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{:>7} {} = {}",
+                        c.count,
+                        opts.base.format(c.reason.value),
+                        labelled.format_reason(c.reason.value, opts.verbose, opts.base)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        "json" => serde_json::to_string_pretty(&counted)?,
+        format => bail!("--counts is not supported by the {format} format, use raw or json"),
+    })
+}
+
+// Loads a baseline of drop reasons from a JSON file, as output by the json dump
+// format. Only the values and names are used.
+fn load_baseline(path: &Path) -> Result<DropReasons> {
+    #[derive(Deserialize)]
+    struct Entry {
+        value: u32,
+        name: String,
+    }
+
+    let file = fs::read_to_string(path)
+        .or_else(|e| bail!("Could not read baseline file {}: {e}", path.display()))?;
+    let entries: Vec<Entry> = serde_json::from_str(&file)
+        .or_else(|e| bail!("Could not parse baseline file {}: {e}", path.display()))?;
+
+    Ok(DropReasons::new(
+        entries.into_iter().map(|e| (e.value, e.name)).collect(),
+        None,
+    ))
+}
+
+// Runs a bpftrace script monitoring drop reasons and translates the raw values
+// it reports into drop reason names, until the script ends.
+fn watch(reasons: &DropReasons, verbose: bool, base: Base) -> Result<()> {
+    let mut child = match Command::new("bpftrace")
+        .arg("-e")
+        .arg(format::bpftrace_watch())
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            bail!("Could not find bpftrace in PATH, it is required by watch")
+        }
+        Err(e) => bail!("Could not run bpftrace: {e}"),
+    };
+
+    // Unwrap as stdout was requested to be piped.
+    for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+        println!("{}", translate_watch(reasons, &line?, verbose, base));
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("bpftrace exited with {status}");
+    }
+    Ok(())
+}
+
+// Translates the raw drop reason of a map entry reported by the watch script,
+// eg. "@drops[tcp_v4_rcv+542, 2]: 3". Other lines are returned unmodified.
+fn translate_watch(reasons: &DropReasons, line: &str, verbose: bool, base: Base) -> String {
+    if let Some((key, count)) = line.rsplit_once("]: ") {
+        if let Some((location, val)) = key.rsplit_once(", ") {
+            if let Ok(val) = val.trim().parse::<u32>() {
+                return format!(
+                    "{location}, {}]: {count}",
+                    reasons.format_reason(val, verbose, base)
+                );
+            }
+        }
+    }
+    line.to_string()
+}
+
+// Copies stdin (or the lines appended to follow, if set) to stdout, appending
+// the name of the known drop reasons matched by the first capture group of
+// pattern, eg. "reason=2" -> "reason=2(NO_SOCKET)".
+#[cfg(feature = "log-filter")]
+fn filter_log(reasons: &DropReasons, pattern: &str, follow: Option<&Path>) -> Result<()> {
+    let re = regex::Regex::new(pattern).or_else(|e| bail!("Invalid pattern {pattern}: {e}"))?;
+    if re.captures_len() < 2 {
+        bail!("Pattern {pattern} has no capture group for the raw value");
+    }
+
+    if let Some(path) = follow {
+        return follow_log(reasons, &re, path);
+    }
+
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        writeln!(stdout, "{}", annotate_log_line(reasons, &re, &line?))?;
+    }
+    Ok(())
+}
+
+// Appends the name of the known drop reasons matched by the first capture
+// group of re to a line (see filter_log).
+#[cfg(feature = "log-filter")]
+fn annotate_log_line(reasons: &DropReasons, re: &regex::Regex, line: &str) -> String {
+    let mut annotated = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for caps in re.captures_iter(line) {
+        let Some(val) = caps.get(1) else {
+            continue;
+        };
+        let Some(name) = parse_value(val.as_str()).ok().and_then(|v| reasons.get(v)) else {
+            continue;
+        };
+
+        annotated.push_str(&line[last..val.end()]);
+        annotated.push_str(&format!("({name})"));
+        last = val.end();
+    }
+    annotated.push_str(&line[last..]);
+    annotated
+}
+
+// Follows a file as tail -f does, annotating the lines appended to it (see
+// filter_log) until interrupted. Lines are only annotated once complete, and
+// the file is read again from its start if it shrinks (eg. truncated when
+// rotated).
+#[cfg(feature = "log-filter")]
+fn follow_log(reasons: &DropReasons, re: &regex::Regex, path: &Path) -> Result<()> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file =
+        fs::File::open(path).or_else(|e| bail!("Could not open {}: {e}", path.display()))?;
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        let read = reader.read_line(&mut line)?;
+        pos += read as u64;
+        if let Some(complete) = line.strip_suffix('\n') {
+            writeln!(stdout, "{}", annotate_log_line(reasons, re, complete))?;
+            line.clear();
+            continue;
+        }
+
+        if read == 0 {
+            if reader.get_ref().metadata()?.len() < pos {
+                pos = reader.seek(SeekFrom::Start(0))?;
+                line.clear();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(250));
+        }
+    }
 }
@@ -0,0 +1,169 @@
+//! Best-effort snapshot of the drop reasons defined by a recent kernel, used
+//! when no BTF file is available. Results reflect the bundled kernel, not the
+//! running one.
+//!
+//! The tables are taken from the vmlinux BTF of the bundled kernel, with their
+//! explicit values. The sub-system drop reasons (eg. mac80211 or openvswitch
+//! ones) are defined in module BTF and are not bundled, only the sub-systems
+//! themselves are.
+
+use std::collections::BTreeMap;
+
+use crate::DropReasons;
+
+/// Kernel version the bundled drop reasons were taken from.
+pub const KERNEL: &str = "6.18";
+
+// Core drop reasons (enum skb_drop_reason), without the sub-system mask.
+const CORE: &[(u32, &str)] = &[
+    (0, "SKB_NOT_DROPPED_YET"),
+    (1, "SKB_CONSUMED"),
+    (2, "SKB_DROP_REASON_NOT_SPECIFIED"),
+    (3, "SKB_DROP_REASON_NO_SOCKET"),
+    (4, "SKB_DROP_REASON_SOCKET_CLOSE"),
+    (5, "SKB_DROP_REASON_SOCKET_FILTER"),
+    (6, "SKB_DROP_REASON_SOCKET_RCVBUFF"),
+    (7, "SKB_DROP_REASON_UNIX_DISCONNECT"),
+    (8, "SKB_DROP_REASON_UNIX_SKIP_OOB"),
+    (9, "SKB_DROP_REASON_PKT_TOO_SMALL"),
+    (10, "SKB_DROP_REASON_TCP_CSUM"),
+    (11, "SKB_DROP_REASON_UDP_CSUM"),
+    (12, "SKB_DROP_REASON_NETFILTER_DROP"),
+    (13, "SKB_DROP_REASON_OTHERHOST"),
+    (14, "SKB_DROP_REASON_IP_CSUM"),
+    (15, "SKB_DROP_REASON_IP_INHDR"),
+    (16, "SKB_DROP_REASON_IP_RPFILTER"),
+    (17, "SKB_DROP_REASON_UNICAST_IN_L2_MULTICAST"),
+    (18, "SKB_DROP_REASON_XFRM_POLICY"),
+    (19, "SKB_DROP_REASON_IP_NOPROTO"),
+    (20, "SKB_DROP_REASON_PROTO_MEM"),
+    (21, "SKB_DROP_REASON_TCP_AUTH_HDR"),
+    (22, "SKB_DROP_REASON_TCP_MD5NOTFOUND"),
+    (23, "SKB_DROP_REASON_TCP_MD5UNEXPECTED"),
+    (24, "SKB_DROP_REASON_TCP_MD5FAILURE"),
+    (25, "SKB_DROP_REASON_TCP_AONOTFOUND"),
+    (26, "SKB_DROP_REASON_TCP_AOUNEXPECTED"),
+    (27, "SKB_DROP_REASON_TCP_AOKEYNOTFOUND"),
+    (28, "SKB_DROP_REASON_TCP_AOFAILURE"),
+    (29, "SKB_DROP_REASON_SOCKET_BACKLOG"),
+    (30, "SKB_DROP_REASON_TCP_FLAGS"),
+    (31, "SKB_DROP_REASON_TCP_ABORT_ON_DATA"),
+    (32, "SKB_DROP_REASON_TCP_ZEROWINDOW"),
+    (33, "SKB_DROP_REASON_TCP_OLD_DATA"),
+    (34, "SKB_DROP_REASON_TCP_OVERWINDOW"),
+    (35, "SKB_DROP_REASON_TCP_OFOMERGE"),
+    (36, "SKB_DROP_REASON_TCP_RFC7323_PAWS"),
+    (37, "SKB_DROP_REASON_TCP_RFC7323_PAWS_ACK"),
+    (38, "SKB_DROP_REASON_TCP_RFC7323_TW_PAWS"),
+    (39, "SKB_DROP_REASON_TCP_RFC7323_TSECR"),
+    (40, "SKB_DROP_REASON_TCP_LISTEN_OVERFLOW"),
+    (41, "SKB_DROP_REASON_TCP_OLD_SEQUENCE"),
+    (42, "SKB_DROP_REASON_TCP_INVALID_SEQUENCE"),
+    (43, "SKB_DROP_REASON_TCP_INVALID_END_SEQUENCE"),
+    (44, "SKB_DROP_REASON_TCP_INVALID_ACK_SEQUENCE"),
+    (45, "SKB_DROP_REASON_TCP_RESET"),
+    (46, "SKB_DROP_REASON_TCP_INVALID_SYN"),
+    (47, "SKB_DROP_REASON_TCP_CLOSE"),
+    (48, "SKB_DROP_REASON_TCP_FASTOPEN"),
+    (49, "SKB_DROP_REASON_TCP_OLD_ACK"),
+    (50, "SKB_DROP_REASON_TCP_TOO_OLD_ACK"),
+    (51, "SKB_DROP_REASON_TCP_ACK_UNSENT_DATA"),
+    (52, "SKB_DROP_REASON_TCP_OFO_QUEUE_PRUNE"),
+    (53, "SKB_DROP_REASON_TCP_OFO_DROP"),
+    (54, "SKB_DROP_REASON_IP_OUTNOROUTES"),
+    (55, "SKB_DROP_REASON_BPF_CGROUP_EGRESS"),
+    (56, "SKB_DROP_REASON_IPV6DISABLED"),
+    (57, "SKB_DROP_REASON_NEIGH_CREATEFAIL"),
+    (58, "SKB_DROP_REASON_NEIGH_FAILED"),
+    (59, "SKB_DROP_REASON_NEIGH_QUEUEFULL"),
+    (60, "SKB_DROP_REASON_NEIGH_DEAD"),
+    (61, "SKB_DROP_REASON_NEIGH_HH_FILLFAIL"),
+    (62, "SKB_DROP_REASON_TC_EGRESS"),
+    (63, "SKB_DROP_REASON_SECURITY_HOOK"),
+    (64, "SKB_DROP_REASON_QDISC_DROP"),
+    (65, "SKB_DROP_REASON_QDISC_OVERLIMIT"),
+    (66, "SKB_DROP_REASON_QDISC_CONGESTED"),
+    (67, "SKB_DROP_REASON_CAKE_FLOOD"),
+    (68, "SKB_DROP_REASON_FQ_BAND_LIMIT"),
+    (69, "SKB_DROP_REASON_FQ_HORIZON_LIMIT"),
+    (70, "SKB_DROP_REASON_FQ_FLOW_LIMIT"),
+    (71, "SKB_DROP_REASON_CPU_BACKLOG"),
+    (72, "SKB_DROP_REASON_XDP"),
+    (73, "SKB_DROP_REASON_TC_INGRESS"),
+    (74, "SKB_DROP_REASON_UNHANDLED_PROTO"),
+    (75, "SKB_DROP_REASON_SKB_CSUM"),
+    (76, "SKB_DROP_REASON_SKB_GSO_SEG"),
+    (77, "SKB_DROP_REASON_SKB_UCOPY_FAULT"),
+    (78, "SKB_DROP_REASON_DEV_HDR"),
+    (79, "SKB_DROP_REASON_DEV_READY"),
+    (80, "SKB_DROP_REASON_FULL_RING"),
+    (81, "SKB_DROP_REASON_NOMEM"),
+    (82, "SKB_DROP_REASON_HDR_TRUNC"),
+    (83, "SKB_DROP_REASON_TAP_FILTER"),
+    (84, "SKB_DROP_REASON_TAP_TXFILTER"),
+    (85, "SKB_DROP_REASON_ICMP_CSUM"),
+    (86, "SKB_DROP_REASON_INVALID_PROTO"),
+    (87, "SKB_DROP_REASON_IP_INADDRERRORS"),
+    (88, "SKB_DROP_REASON_IP_INNOROUTES"),
+    (89, "SKB_DROP_REASON_IP_LOCAL_SOURCE"),
+    (90, "SKB_DROP_REASON_IP_INVALID_SOURCE"),
+    (91, "SKB_DROP_REASON_IP_LOCALNET"),
+    (92, "SKB_DROP_REASON_IP_INVALID_DEST"),
+    (93, "SKB_DROP_REASON_PKT_TOO_BIG"),
+    (94, "SKB_DROP_REASON_DUP_FRAG"),
+    (95, "SKB_DROP_REASON_FRAG_REASM_TIMEOUT"),
+    (96, "SKB_DROP_REASON_FRAG_TOO_FAR"),
+    (97, "SKB_DROP_REASON_TCP_MINTTL"),
+    (98, "SKB_DROP_REASON_IPV6_BAD_EXTHDR"),
+    (99, "SKB_DROP_REASON_IPV6_NDISC_FRAG"),
+    (100, "SKB_DROP_REASON_IPV6_NDISC_HOP_LIMIT"),
+    (101, "SKB_DROP_REASON_IPV6_NDISC_BAD_CODE"),
+    (102, "SKB_DROP_REASON_IPV6_NDISC_BAD_OPTIONS"),
+    (103, "SKB_DROP_REASON_IPV6_NDISC_NS_OTHERHOST"),
+    (104, "SKB_DROP_REASON_QUEUE_PURGE"),
+    (105, "SKB_DROP_REASON_TC_COOKIE_ERROR"),
+    (106, "SKB_DROP_REASON_PACKET_SOCK_ERROR"),
+    (107, "SKB_DROP_REASON_TC_CHAIN_NOTFOUND"),
+    (108, "SKB_DROP_REASON_TC_RECLASSIFY_LOOP"),
+    (109, "SKB_DROP_REASON_VXLAN_INVALID_HDR"),
+    (110, "SKB_DROP_REASON_VXLAN_VNI_NOT_FOUND"),
+    (111, "SKB_DROP_REASON_MAC_INVALID_SOURCE"),
+    (112, "SKB_DROP_REASON_VXLAN_ENTRY_EXISTS"),
+    (113, "SKB_DROP_REASON_NO_TX_TARGET"),
+    (114, "SKB_DROP_REASON_IP_TUNNEL_ECN"),
+    (115, "SKB_DROP_REASON_TUNNEL_TXINFO"),
+    (116, "SKB_DROP_REASON_LOCAL_MAC"),
+    (117, "SKB_DROP_REASON_ARP_PVLAN_DISABLE"),
+    (118, "SKB_DROP_REASON_MAC_IEEE_MAC_CONTROL"),
+    (119, "SKB_DROP_REASON_BRIDGE_INGRESS_STP_STATE"),
+    (120, "SKB_DROP_REASON_CAN_RX_INVALID_FRAME"),
+    (121, "SKB_DROP_REASON_CANFD_RX_INVALID_FRAME"),
+    (122, "SKB_DROP_REASON_CANXL_RX_INVALID_FRAME"),
+    (123, "SKB_DROP_REASON_PFMEMALLOC"),
+    (124, "SKB_DROP_REASON_DUALPI2_STEP_DROP"),
+    (125, "SKB_DROP_REASON_PSP_INPUT"),
+    (126, "SKB_DROP_REASON_PSP_OUTPUT"),
+    (127, "SKB_DROP_REASON_RECURSION_LIMIT"),
+    (128, "SKB_DROP_REASON_MAX"),
+];
+
+// Sub-systems (enum skb_drop_reason_subsys).
+const SUBSYS: &[(u32, &str)] = &[
+    (0, "SKB_DROP_REASON_SUBSYS_CORE"),
+    (1, "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE"),
+    (2, "SKB_DROP_REASON_SUBSYS_OPENVSWITCH"),
+    (3, "SKB_DROP_REASON_SUBSYS_NUM"),
+];
+
+// Converts a list of enum variants into a (val <> name) map.
+fn to_map(members: &[(u32, &str)]) -> BTreeMap<u32, String> {
+    members
+        .iter()
+        .map(|(val, name)| (*val, name.to_string()))
+        .collect()
+}
+
+/// Returns the bundled drop reasons.
+pub fn reasons() -> DropReasons {
+    DropReasons::new(to_map(CORE), Some(to_map(SUBSYS)))
+}
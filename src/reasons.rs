@@ -0,0 +1,1402 @@
+//! Drop reasons parsing from BTF files and helpers to work with them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    ops::{Bound, RangeBounds},
+    path::Path,
+};
+
+use anyhow::{bail, Result};
+use btf_rs::{utils::BtfCollection, Type};
+use serde::Serialize;
+
+use crate::{
+    source::{load_dir, BtfSource, Dir},
+    stability::{self, Stability},
+};
+
+/// Keep this in-sync with the kernel definition in include/net/dropreason.h
+///
+/// Used to detect if the kernel supports more drop reasons than we know of.
+pub const SKB_DROP_REASON_SUBSYS_NUM: usize = 5;
+// Terminator of the kernel sub-systems definition.
+const SKB_DROP_REASON_SUBSYS_NUM_NAME: &str = "SKB_DROP_REASON_SUBSYS_NUM";
+
+// Known drop reason definitions in the kernel (except for core that is
+// mandatory).
+const NON_CORE_DROP_REASONS: &[&str] = &["mac80211_drop_reason", "ovs_drop_reason"];
+
+// Core reasons reporting a consumed skb (ie. not a drop), as named in BTF and
+// in the tracepoint format (printk names lack the SKB_ prefix).
+const CONSUME_REASONS: &[&str] = &["SKB_CONSUMED", "CONSUMED"];
+// Core reasons not reporting a drop nor a consumed skb: an skb that is neither
+// dropped nor consumed and the SKB_DROP_REASON_MAX sentinel.
+const NOT_CLASSIFIED_REASONS: &[&str] = &[
+    "SKB_NOT_DROPPED_YET",
+    "NOT_DROPPED_YET",
+    "SKB_DROP_REASON_MAX",
+    "MAX",
+];
+
+// Special drop reason value used as a mask for the sub-system bits
+// (SKB_DROP_REASON_SUBSYS_MASK).
+const SKB_DROP_REASON_SUBSYS_MASK: u32 = 0xffff0000;
+const SKB_DROP_REASON_SUBSYS_MASK_NAME: &str = "SKB_DROP_REASON_SUBSYS_MASK";
+
+/// Numeric base used when displaying raw values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Base {
+    /// Decimal, eg. `65538`.
+    #[default]
+    Dec,
+    /// Hexadecimal padded to 8 digits for the sub-system bits to be aligned,
+    /// eg. `0x00010002`.
+    Hex,
+}
+
+impl Base {
+    /// Formats a raw value in the base.
+    pub fn format(self, val: u32) -> String {
+        match self {
+            Base::Dec => val.to_string(),
+            Base::Hex => format!("{val:#010x}"),
+        }
+    }
+}
+
+/// Value of an enum member as found in BTF (see [`raw_enum`]), signed or not
+/// depending on the enum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RawValue {
+    /// Member of a signed enum.
+    Signed(i64),
+    /// Member of an unsigned enum.
+    Unsigned(u64),
+}
+
+impl RawValue {
+    /// Formats the value in a base. Unlike [`Base::format`], values are not
+    /// truncated to 32 bits and negative ones keep their sign, eg. `-0x00000001`.
+    pub fn format(self, base: Base) -> String {
+        match (self, base) {
+            (RawValue::Signed(val), Base::Dec) => val.to_string(),
+            (RawValue::Unsigned(val), Base::Dec) => val.to_string(),
+            (RawValue::Signed(val), Base::Hex) if val < 0 => {
+                format!("-{:#010x}", val.unsigned_abs())
+            }
+            (RawValue::Signed(val), Base::Hex) => format!("{val:#010x}"),
+            (RawValue::Unsigned(val), Base::Hex) => format!("{val:#010x}"),
+        }
+    }
+}
+
+/// Policy applied when a sub-system reason reuses an already known value (see
+/// [`DropReasons::apply_merge_strategy`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MergeStrategy {
+    /// Keep the first definition, ie. the core one if any.
+    #[default]
+    KeepFirst,
+    /// Let the sub-system reasons replace the already known ones.
+    PreferSubsys,
+    /// Fail on any collision.
+    Error,
+}
+
+/// A single drop reason.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DropReason {
+    /// Raw value of the reason.
+    pub value: u32,
+    /// Index of the reason within its sub-system (the lower 16 bits of its
+    /// value), equal to the value for core reasons.
+    pub index: u32,
+    /// Name of the reason, as defined in the kernel enums.
+    pub name: String,
+    /// Kernel name of the sub-system the reason belongs to, if known (see
+    /// [`DropReasons::rename_subsys`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsystem: Option<String>,
+    /// Human readable description of the reason, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Stability of the reason, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stability: Option<Stability>,
+    /// Whether the reason reports a consumed skb rather than a drop, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_consume: Option<bool>,
+    /// Display label of the reason, if renamed (see [`DropReasons::rename`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Name of the kernel enum defining the reason (eg. `skb_drop_reason`) or
+    /// `printk`, if known (see [`DropReasons::source`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Former kernel names of the reason, if it was renamed (see
+    /// [`DropReasons::aliases`]).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// Result of resolving a raw value, which might not match a known reason. Names
+/// are borrowed from the [`DropReasons`] the value was resolved with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Resolved<'a> {
+    /// Raw value that was resolved.
+    pub value: u32,
+    /// Name of the reason, if known. Also set for the sub-system mask, which
+    /// is not a real reason (`known` is false).
+    pub name: Option<&'a str>,
+    /// Kernel name of the sub-system matching the value, if known.
+    pub subsystem: Option<&'a str>,
+    /// Whether the value matches a known drop reason.
+    pub known: bool,
+}
+
+/// Breakdown of a raw value into its sub-system and index parts (see
+/// [`DropReasons::decode`]), each resolved independently.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Decoded {
+    /// Raw value that was decoded.
+    pub value: u32,
+    /// Sub-system part of the value (its upper 16 bits), as an id.
+    pub subsystem_id: u32,
+    /// Kernel name of the sub-system, if known.
+    pub subsystem: Option<String>,
+    /// Reason index part of the value (its lower 16 bits).
+    pub index: u32,
+    /// Name of the reason matching the whole value, if known.
+    pub reason: Option<String>,
+}
+
+/// Drop reason whose name changed between two sets of drop reasons.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Renamed {
+    /// Raw value of the reason in the new set. It is identical in the old one,
+    /// unless the rename was detected using the known former names of the
+    /// reason (see [`DropReasons::aliases`]).
+    pub value: u32,
+    /// Name of the reason in the old set.
+    pub from: String,
+    /// Name of the reason in the new set.
+    pub to: String,
+}
+
+/// Changes between two sets of drop reasons (see [`DropReasons::diff`]),
+/// reasons being matched by raw value. Each change is sorted by ascending raw
+/// value.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ReasonDiff {
+    /// Reasons only found in the new set.
+    pub added: Vec<DropReason>,
+    /// Reasons only found in the old set.
+    pub removed: Vec<DropReason>,
+    /// Reasons found in both sets with a different name.
+    pub renamed: Vec<Renamed>,
+}
+
+impl ReasonDiff {
+    /// Returns true if both sets define the same drop reasons.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+
+    // Reports the removed and added reasons which are the former and current
+    // names of the same reason as renamed, regardless of their values.
+    #[cfg(feature = "aliases")]
+    fn match_aliases(&mut self) {
+        let is_alias = |a: &str, b: &str| {
+            crate::aliases::current(a) == Some(b) || crate::aliases::current(b) == Some(a)
+        };
+
+        let mut removed = Vec::new();
+        for old in std::mem::take(&mut self.removed) {
+            match self
+                .added
+                .iter()
+                .position(|new| is_alias(&old.name, &new.name))
+            {
+                Some(i) => {
+                    let new = self.added.remove(i);
+                    self.renamed.push(Renamed {
+                        value: new.value,
+                        from: old.name,
+                        to: new.name,
+                    });
+                }
+                None => removed.push(old),
+            }
+        }
+        self.removed = removed;
+        self.renamed.sort_by_key(|r| r.value);
+    }
+
+    // Former names are only known with the aliases feature.
+    #[cfg(not(feature = "aliases"))]
+    fn match_aliases(&mut self) {}
+}
+
+// Formats the changes one per line, prefixed by +, - or ~ for the added,
+// removed and renamed reasons.
+impl fmt::Display for ReasonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.added
+            .iter()
+            .try_for_each(|r| writeln!(f, "+ {} = {}", r.value, r.name))?;
+        self.removed
+            .iter()
+            .try_for_each(|r| writeln!(f, "- {} = {}", r.value, r.name))?;
+        self.renamed
+            .iter()
+            .try_for_each(|r| writeln!(f, "~ {} = {} -> {}", r.value, r.from, r.to))
+    }
+}
+
+/// Drop reasons and their sub-systems.
+///
+/// Reasons are identified by their raw value, which encodes the sub-system in
+/// its upper 16 bits.
+#[derive(Clone)]
+pub struct DropReasons {
+    reasons: BTreeMap<u32, String>,
+    subsys: Option<BTreeMap<u32, String>>,
+    // Sub-system reasons that were not added as their value was already
+    // known, as (value, kept name, dropped name).
+    duplicates: Vec<(u32, String, String)>,
+    // Kernel enums defining the dropped names of the above.
+    duplicate_sources: Vec<String>,
+    // Display labels of the renamed reasons.
+    labels: BTreeMap<u32, String>,
+    // Display labels of the renamed sub-systems.
+    subsys_labels: BTreeMap<u32, String>,
+    // Kernel names of the reasons and sub-systems displayed using their label,
+    // in the views returned by DropReasons::labelled.
+    kernel_names: BTreeMap<u32, String>,
+    subsys_kernel_names: BTreeMap<u32, String>,
+    // Kernel enums defining the reasons, when parsed from BTF.
+    sources: BTreeMap<u32, String>,
+    // Enum members skipped when parsing, as ENUM::MEMBER, their value not
+    // fitting in 32 bits.
+    out_of_range: Vec<String>,
+    // Name of the sub-system mask member removed when parsing, if any.
+    mask: Option<String>,
+}
+
+impl DropReasons {
+    /// Builds a drop reasons representation from already known reasons and
+    /// sub-systems.
+    pub fn new(reasons: BTreeMap<u32, String>, subsys: Option<BTreeMap<u32, String>>) -> Self {
+        Self {
+            reasons,
+            subsys,
+            duplicates: Vec::new(),
+            duplicate_sources: Vec::new(),
+            labels: BTreeMap::new(),
+            subsys_labels: BTreeMap::new(),
+            kernel_names: BTreeMap::new(),
+            subsys_kernel_names: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            out_of_range: Vec::new(),
+            mask: None,
+        }
+    }
+
+    /// Parses the core drop reasons only, from the base `vmlinux` BTF file
+    /// found in `dir`. The non-core reasons and the sub-systems are not
+    /// parsed, making this the fastest way to load drop reasons.
+    pub fn from_dir_core<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let btf = BtfCollection::from_file(dir.as_ref().join("vmlinux"))
+            .or_else(|e| bail!("Could not parse BTF files: {e}"))?;
+
+        let mut out_of_range = Vec::new();
+        let (reasons, mask) = parse_core(&btf, None, &mut out_of_range)?;
+        let sources = reasons
+            .keys()
+            .map(|val| (*val, "skb_drop_reason".to_string()))
+            .collect();
+        Ok(Self {
+            sources,
+            out_of_range,
+            mask,
+            ..Self::new(reasons, None)
+        })
+    }
+
+    /// Returns the drop reasons and sub-systems tables, indexed by raw value
+    /// and sub-system id respectively. This is the reverse of
+    /// [`DropReasons::new`].
+    pub fn into_parts(self) -> (BTreeMap<u32, String>, Option<BTreeMap<u32, String>>) {
+        (self.reasons, self.subsys)
+    }
+
+    /// Parses the drop reasons and sub-systems from the BTF files found in
+    /// `dir`.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        Self::from_source(&Dir::new(dir))
+    }
+
+    /// Parses the drop reasons and sub-systems from the BTF files found in
+    /// `dir`, only loading the module BTF files whose name matches one of the
+    /// given glob `patterns` (supporting `*` and `?`). The base `vmlinux` BTF
+    /// file is always loaded.
+    pub fn from_dir_matching<P: AsRef<Path>>(dir: P, patterns: &[String]) -> Result<Self> {
+        Self::from_dir_with_progress(dir, Some(patterns), |_, _| ())
+    }
+
+    /// Parses the drop reasons and sub-systems from the BTF files found in
+    /// `dir`, calling `progress` with the number of BTF files loaded so far
+    /// and the total number of files to load after each one. If `patterns` is
+    /// set, only the module BTF files matching one of them are loaded (see
+    /// [`DropReasons::from_dir_matching`]).
+    pub fn from_dir_with_progress<P, F>(
+        dir: P,
+        patterns: Option<&[String]>,
+        progress: F,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize, usize),
+    {
+        Self::from_collection(&load_dir(dir.as_ref(), patterns, progress)?)
+    }
+
+    /// Parses the drop reasons and sub-systems from the BTF files found in
+    /// `dir`, as [`DropReasons::from_dir_with_progress`], but using the
+    /// definition of the BTF file named `prefer` (eg. `vmlinux` or a module
+    /// name) for the enums defined in more than one BTF file, when it defines
+    /// them. Otherwise, definitions are selected as usual.
+    pub fn from_dir_preferring<P, F>(
+        dir: P,
+        patterns: Option<&[String]>,
+        prefer: &str,
+        progress: F,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize, usize),
+    {
+        Self::from_collection_with(&load_dir(dir.as_ref(), patterns, progress)?, Some(prefer))
+    }
+
+    /// Parses the core drop reasons from a tracefs event format file (eg.
+    /// `events/skb/kfree_skb/format`), using the symbolic names of the reason
+    /// field in its print format. This is a fallback for when BTF is not
+    /// available: names are the ones printed by the tracepoint, without their
+    /// `SKB_DROP_REASON_` prefix, and sub-systems are not known. The source of
+    /// the reasons is reported as `printk` (see [`DropReasons::source`]).
+    pub fn from_tracefs<P: AsRef<Path>>(format: P) -> Result<Self> {
+        let format = format.as_ref();
+        let content = fs::read_to_string(format)
+            .or_else(|e| bail!("Could not read {}: {e}", format.display()))?;
+
+        let reasons = parse_print_symbolic(&content)
+            .or_else(|e| bail!("Could not parse {}: {e}", format.display()))?;
+        let sources = reasons
+            .keys()
+            .map(|val| (*val, "printk".to_string()))
+            .collect();
+        Ok(Self {
+            sources,
+            ..Self::new(reasons, None)
+        })
+    }
+
+    /// Parses the drop reasons and sub-systems from a single base BTF blob
+    /// (eg. the content of `/sys/kernel/btf/vmlinux`) held in memory, eg. when
+    /// it comes from a database, the network or an embedded asset. Only its
+    /// drop reasons are known, not the ones of modules.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let btf = BtfCollection::from_bytes("vmlinux", bytes)
+            .or_else(|e| bail!("Could not parse BTF: {e}"))?;
+        Self::from_collection(&btf)
+    }
+
+    /// Parses the drop reasons and sub-systems from the BTF files loaded by
+    /// `source` (see the [`source`](crate::source) module), eg. one provided
+    /// by the caller.
+    pub fn from_source<S: BtfSource + ?Sized>(source: &S) -> Result<Self> {
+        Self::from_collection(&source.load()?)
+    }
+
+    /// Parses the drop reasons and sub-systems from an already loaded BTF
+    /// collection, eg. when the caller loads BTF for other purposes too.
+    ///
+    /// This is the full sequence used by all the BTF loaders: the core
+    /// reasons are parsed (failing if not found), the sub-system mask is
+    /// removed, the known non-core reasons are added without overwriting
+    /// already known values (see [`DropReasons::duplicates`]) and the
+    /// sub-systems are parsed. Use [`DropReasons::into_parts`] to get the
+    /// resulting tables.
+    pub fn from_collection(btf: &BtfCollection) -> Result<Self> {
+        Self::from_collection_with(btf, None)
+    }
+
+    // Parses the drop reasons and sub-systems from a BTF collection,
+    // preferring the definitions of the given BTF file if set (see
+    // parse_enum).
+    fn from_collection_with(btf: &BtfCollection, prefer: Option<&str>) -> Result<Self> {
+        let mut out_of_range = Vec::new();
+        let (mut reasons, mask) = parse_core(btf, prefer, &mut out_of_range)?;
+        let mut sources: BTreeMap<_, _> = reasons
+            .keys()
+            .map(|val| (*val, "skb_drop_reason".to_string()))
+            .collect();
+
+        // Parse non-core drop reasons. Failing to parse one of those isn't
+        // fatal, as the core ones are still usable.
+        let mut duplicates = Vec::new();
+        let mut duplicate_sources = Vec::new();
+        for r#enum in NON_CORE_DROP_REASONS {
+            match parse_enum(btf, r#enum, prefer, &mut out_of_range) {
+                Ok(Some(mut subsys_reasons)) => {
+                    while let Some((val, reason)) = subsys_reasons.pop_first() {
+                        // Do not overwrite known values. Some sub-system do
+                        // this for reusing some of the very generic core
+                        // reasons. Eg. SKB_CONSUMED.
+                        match reasons.get(&val) {
+                            Some(kept) => {
+                                duplicates.push((val, kept.clone(), reason));
+                                duplicate_sources.push(r#enum.to_string());
+                            }
+                            None => {
+                                reasons.insert(val, reason);
+                                sources.insert(val, r#enum.to_string());
+                            }
+                        }
+                    }
+                }
+                Ok(None) => log::debug!("{} not found", r#enum),
+                Err(e) => log::warn!("could not parse {}: {e}", r#enum),
+            }
+        }
+
+        // Get a list of all the known subsystems that can register non-core
+        // drop reasons. This might return more elements than the ones we know
+        // of (if we haven't added support for those yet).
+        let subsys = parse_enum(btf, "skb_drop_reason_subsys", prefer, &mut out_of_range)
+            .unwrap_or_else(|e| {
+                log::warn!("could not parse skb_drop_reason_subsys: {e}");
+                None
+            });
+
+        Ok(Self {
+            reasons,
+            subsys,
+            duplicates,
+            duplicate_sources,
+            labels: BTreeMap::new(),
+            subsys_labels: BTreeMap::new(),
+            kernel_names: BTreeMap::new(),
+            subsys_kernel_names: BTreeMap::new(),
+            sources,
+            out_of_range,
+            mask,
+        })
+    }
+
+    /// Merges additional drop reasons. Already known values are kept unless
+    /// `force` is set, in which case they are overwritten. Returns the list of
+    /// colliding values as `(value, known name, merged name)`, whether they
+    /// were overwritten or not.
+    pub fn merge(
+        &mut self,
+        reasons: BTreeMap<u32, String>,
+        force: bool,
+    ) -> Vec<(u32, String, String)> {
+        let mut collisions = Vec::new();
+
+        for (val, name) in reasons.into_iter() {
+            match self.reasons.get_mut(&val) {
+                Some(known) => {
+                    if *known != name {
+                        collisions.push((val, known.clone(), name.clone()));
+                    }
+                    if force {
+                        *known = name;
+                        self.sources.remove(&val);
+                    }
+                }
+                None => {
+                    self.reasons.insert(val, name);
+                }
+            }
+        }
+
+        collisions
+    }
+
+    /// Sets display labels for known drop reasons, given a map of raw values
+    /// to labels. Labels are kept apart from the kernel names, which are still
+    /// used everywhere (eg. in [`DropReasons::get`], when comparing or
+    /// fingerprinting reasons), and are only displayed by the views returned
+    /// by [`DropReasons::labelled`]. They are reported in
+    /// [`DropReason::label`]. Returns the values not matching a known reason,
+    /// which are not renamed.
+    pub fn rename(&mut self, labels: BTreeMap<u32, String>) -> Vec<u32> {
+        let mut unknown = Vec::new();
+
+        for (val, label) in labels.into_iter() {
+            match self.reasons.contains_key(&val) {
+                true => {
+                    self.labels.insert(val, label);
+                }
+                false => unknown.push(val),
+            }
+        }
+
+        unknown
+    }
+
+    /// Sets display labels for known sub-systems, given a map of sub-system
+    /// ids to labels (eg. `mac80211`), as [`DropReasons::rename`] does for
+    /// reasons. Returns the ids not matching a known sub-system, which are not
+    /// renamed.
+    pub fn rename_subsys(&mut self, labels: BTreeMap<u32, String>) -> Vec<u32> {
+        let mut unknown = Vec::new();
+
+        for (id, label) in labels.into_iter() {
+            match self.subsys_name(id).is_some() {
+                true => {
+                    self.subsys_labels.insert(id, label);
+                }
+                false => unknown.push(id),
+            }
+        }
+
+        unknown
+    }
+
+    /// Returns the display label of a drop reason given its raw value, if it
+    /// was renamed (see [`DropReasons::rename`]).
+    pub fn label(&self, val: u32) -> Option<&str> {
+        self.labels.get(&val).map(String::as_str)
+    }
+
+    /// Returns a view of the drop reasons using their labels, if renamed, as
+    /// names (eg. in [`DropReasons::get`] and when formatting), for outputs
+    /// read by humans. [`DropReason::name`] and [`DropReason::subsystem`] keep
+    /// the kernel names.
+    pub fn labelled(&self) -> DropReasons {
+        let mut view = self.clone();
+
+        for (val, label) in self.labels.iter() {
+            if let Some(name) = view.reasons.insert(*val, label.clone()) {
+                view.kernel_names.entry(*val).or_insert(name);
+            }
+        }
+        if let Some(subsys) = view.subsys.as_mut() {
+            for (id, label) in self.subsys_labels.iter() {
+                if let Some(name) = subsys.insert(*id, label.clone()) {
+                    view.subsys_kernel_names.entry(*id).or_insert(name);
+                }
+            }
+        }
+
+        view
+    }
+
+    /// Only keeps the drop reasons for which `f` returns true.
+    pub fn retain<F: FnMut(u32, &str) -> bool>(&mut self, mut f: F) {
+        self.reasons.retain(|val, name| f(*val, name));
+    }
+
+    /// Only keeps the drop reasons whose raw value is in `range`. Panics if
+    /// the range start is greater than its end.
+    pub fn retain_range<R: RangeBounds<u32>>(&mut self, range: R) {
+        self.reasons = self
+            .reasons
+            .range(range)
+            .map(|(val, name)| (*val, name.clone()))
+            .collect();
+    }
+
+    /// Removes drop reasons whose name is already used by a reason with a
+    /// lower value. Returns the removed reasons as `(value, kept value,
+    /// name)`.
+    pub fn dedup(&mut self) -> Vec<(u32, u32, String)> {
+        let mut seen: HashMap<String, u32> = HashMap::new();
+        let mut removed = Vec::new();
+
+        self.reasons.retain(|val, name| match seen.get(name) {
+            Some(kept) => {
+                removed.push((*val, *kept, name.clone()));
+                false
+            }
+            None => {
+                seen.insert(name.clone(), *val);
+                true
+            }
+        });
+
+        removed
+    }
+
+    /// Only keeps the first `len` drop reasons, by ascending raw value.
+    pub fn truncate(&mut self, len: usize) {
+        if let Some((val, _)) = self.reasons.iter().nth(len) {
+            let val = *val;
+            self.reasons.split_off(&val);
+        }
+    }
+
+    /// Returns the sub-system reasons that were not added when parsing, as
+    /// their value was already defined (eg. by the core reasons), as
+    /// `(value, kept name, dropped name)`.
+    pub fn duplicates(&self) -> &[(u32, String, String)] {
+        &self.duplicates
+    }
+
+    /// Adds back the sub-system mask (`SKB_DROP_REASON_SUBSYS_MASK`), which is
+    /// not a real reason and is removed when parsing, eg. to inspect the core
+    /// enum as defined in BTF. Returns false if the kernel does not define it.
+    pub fn keep_mask(&mut self) -> bool {
+        match &self.mask {
+            Some(name) => {
+                self.reasons
+                    .insert(SKB_DROP_REASON_SUBSYS_MASK, name.clone());
+                self.sources
+                    .insert(SKB_DROP_REASON_SUBSYS_MASK, "skb_drop_reason".to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the enum members (as `ENUM::MEMBER`) that were skipped when
+    /// parsing, as their value does not fit in 32 bits. A warning is emitted
+    /// for each of them.
+    pub fn out_of_range(&self) -> &[String] {
+        &self.out_of_range
+    }
+
+    /// Applies a merge strategy to the sub-system reasons that were not added
+    /// when parsing (see [`DropReasons::duplicates`]), the parsing itself
+    /// always keeping the first definition. With
+    /// [`MergeStrategy::PreferSubsys`] the duplicates are added, replacing
+    /// the known reasons which are then reported as the duplicates instead.
+    /// With [`MergeStrategy::Error`] an error is returned if any duplicate
+    /// exists.
+    pub fn apply_merge_strategy(&mut self, strategy: MergeStrategy) -> Result<()> {
+        match strategy {
+            MergeStrategy::KeepFirst => (),
+            MergeStrategy::PreferSubsys => {
+                let duplicates = self
+                    .duplicates
+                    .iter_mut()
+                    .zip(self.duplicate_sources.iter_mut());
+                for ((val, kept, dropped), source) in duplicates {
+                    if let Some(known) = self.reasons.get_mut(val) {
+                        *kept = std::mem::replace(known, dropped.clone());
+                        std::mem::swap(kept, dropped);
+                        // The enum of the replaced reason is now the one of
+                        // the duplicate.
+                        if let Some(previous) = self.sources.insert(*val, source.clone()) {
+                            *source = previous;
+                        }
+                    }
+                }
+            }
+            MergeStrategy::Error => {
+                if let Some((val, kept, dropped)) = self.duplicates.first() {
+                    bail!("Drop reason value {val} is defined as both {kept} and {dropped}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of known drop reasons.
+    pub fn len(&self) -> usize {
+        self.reasons.len()
+    }
+
+    /// Returns true if no drop reason is known.
+    pub fn is_empty(&self) -> bool {
+        self.reasons.is_empty()
+    }
+
+    /// Returns the name of a drop reason given its raw value, if known.
+    pub fn get(&self, val: u32) -> Option<&str> {
+        self.reasons.get(&val).map(String::as_str)
+    }
+
+    /// Returns the raw value of a drop reason given its name, if known. If
+    /// multiple reasons share the same name, the lowest value is returned.
+    /// Reasons given a label match both their label and kernel name. With the
+    /// `aliases` feature, the former name of a renamed reason also matches it
+    /// (see [`DropReasons::aliases`]).
+    pub fn value_of(&self, name: &str) -> Option<u32> {
+        let matches =
+            |val: u32| self.kernel_name(val) == Some(name) || self.label(val) == Some(name);
+        match self.reasons.keys().find(|val| matches(**val)) {
+            Some(val) => Some(*val),
+            #[cfg(feature = "aliases")]
+            None => crate::aliases::current(name).and_then(|name| self.value_of(name)),
+            #[cfg(not(feature = "aliases"))]
+            None => None,
+        }
+    }
+
+    /// Returns the former kernel names of a drop reason given its raw value,
+    /// if it was renamed.
+    #[cfg(feature = "aliases")]
+    pub fn aliases(&self, val: u32) -> Vec<&'static str> {
+        self.kernel_name(val)
+            .map(|name| crate::aliases::former(name).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the former kernel names of a drop reason given its raw value,
+    /// if it was renamed. Former names are only available with the `aliases`
+    /// feature.
+    #[cfg(not(feature = "aliases"))]
+    pub fn aliases(&self, _val: u32) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Returns the description of a drop reason given its raw value, if known.
+    #[cfg(feature = "descriptions")]
+    pub fn description(&self, val: u32) -> Option<&'static str> {
+        self.kernel_name(val).and_then(crate::description::get)
+    }
+
+    /// Returns the description of a drop reason given its raw value, if known.
+    /// Descriptions are only available with the `descriptions` feature.
+    #[cfg(not(feature = "descriptions"))]
+    pub fn description(&self, _val: u32) -> Option<&'static str> {
+        None
+    }
+
+    // Returns the kernel name of a drop reason given its raw value, if known,
+    // regardless of it being renamed.
+    fn kernel_name(&self, val: u32) -> Option<&str> {
+        let name = self.get(val)?;
+        Some(self.kernel_names.get(&val).map_or(name, String::as_str))
+    }
+
+    /// Returns the name of the kernel enum defining a drop reason given its
+    /// raw value, eg. `skb_drop_reason` for the core reasons or
+    /// `ovs_drop_reason`, or `printk` for the reasons parsed from a tracepoint
+    /// print format (see [`DropReasons::from_tracefs`]). Only known for the
+    /// reasons not overwritten by [`DropReasons::merge`].
+    pub fn source(&self, val: u32) -> Option<&str> {
+        self.get(val)?;
+        self.sources.get(&val).map(String::as_str)
+    }
+
+    /// Returns all the definitions of a raw value found when parsing, as
+    /// `(enum name, reason name)`, eg. to disambiguate a value defined by
+    /// both the core and a sub-system enum which the merged view collapses
+    /// (see [`DropReasons::duplicates`]). The kept definition comes first,
+    /// then the dropped ones in the order the enums were parsed. Reasons
+    /// whose enum is not known (see [`DropReasons::source`]) are omitted.
+    pub fn definitions(&self, val: u32) -> Vec<(&str, &str)> {
+        let mut definitions = Vec::new();
+        if let (Some(source), Some(name)) = (self.source(val), self.kernel_name(val)) {
+            definitions.push((source, name));
+        }
+        // The sub-system mask is removed when parsing, unless kept.
+        if let (Some(mask), None, SKB_DROP_REASON_SUBSYS_MASK) = (&self.mask, self.get(val), val) {
+            definitions.push(("skb_drop_reason", mask.as_str()));
+        }
+        self.duplicates
+            .iter()
+            .zip(&self.duplicate_sources)
+            .filter(|((dup, _, _), _)| *dup == val)
+            .for_each(|((_, _, dropped), source)| {
+                definitions.push((source.as_str(), dropped.as_str()))
+            });
+        definitions
+    }
+
+    /// Returns the stability of a drop reason given its raw value, if known.
+    pub fn stability(&self, val: u32) -> Option<Stability> {
+        self.kernel_name(val)
+            .and_then(|name| stability::get(val, name))
+    }
+
+    /// Returns whether a drop reason given its raw value reports a consumed
+    /// skb (eg. `SKB_CONSUMED`) rather than a drop, if known. Only the core
+    /// reasons are classified, except `SKB_NOT_DROPPED_YET` and the
+    /// `SKB_DROP_REASON_MAX` sentinel.
+    pub fn is_consume(&self, val: u32) -> Option<bool> {
+        match self.kernel_name(val) {
+            Some(name) if CONSUME_REASONS.contains(&name) => Some(true),
+            Some(name) if val >> 16 == 0 && !NOT_CLASSIFIED_REASONS.contains(&name) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the kernel defines more sub-systems than we know of.
+    /// The kernel definition is sized using its `SKB_DROP_REASON_SUBSYS_NUM`
+    /// terminator when available, by counting its members otherwise.
+    pub fn unknown_subsys(&self) -> bool {
+        self.subsys.as_ref().is_some_and(|subsys| {
+            // Members go from 0 to the terminator included.
+            let num = match subsys
+                .keys()
+                .find(|id| self.subsys_kernel_name(**id) == Some(SKB_DROP_REASON_SUBSYS_NUM_NAME))
+            {
+                Some(num) => *num as usize + 1,
+                None => subsys.len(),
+            };
+            num > SKB_DROP_REASON_SUBSYS_NUM
+        })
+    }
+
+    /// Returns the name of a sub-system given its id, if known.
+    pub fn subsys_name(&self, id: u32) -> Option<&str> {
+        self.subsys
+            .as_ref()
+            .and_then(|subsys| subsys.get(&id))
+            .map(String::as_str)
+    }
+
+    // Returns the kernel name of a sub-system given its id, if known,
+    // regardless of it being renamed.
+    fn subsys_kernel_name(&self, id: u32) -> Option<&str> {
+        let name = self.subsys_name(id)?;
+        Some(
+            self.subsys_kernel_names
+                .get(&id)
+                .map_or(name, String::as_str),
+        )
+    }
+
+    /// Returns the name of the sub-system a raw value belongs to, if known.
+    /// The value does not have to match a known reason.
+    pub fn subsystem_for(&self, val: u32) -> Option<&str> {
+        self.subsys_name(val >> 16)
+    }
+
+    /// Returns the known drop reasons closest to a raw value within its
+    /// sub-system, being the closest lower and higher ones (if any), by
+    /// ascending raw value. The value itself is not reported.
+    pub fn nearest(&self, val: u32) -> Vec<(u32, &str)> {
+        let start = val & SKB_DROP_REASON_SUBSYS_MASK;
+        let end = val | !SKB_DROP_REASON_SUBSYS_MASK;
+
+        let lower = self
+            .reasons
+            .range((Bound::Included(start), Bound::Excluded(val)))
+            .next_back();
+        let higher = self
+            .reasons
+            .range((Bound::Excluded(val), Bound::Included(end)))
+            .next();
+
+        lower
+            .into_iter()
+            .chain(higher)
+            .map(|(val, name)| (*val, name.as_str()))
+            .collect()
+    }
+
+    /// Returns the id of a sub-system given its name, if known. Names are
+    /// matched case-insensitively, with or without their
+    /// `SKB_DROP_REASON_SUBSYS_` prefix (eg. `OPENVSWITCH`). Renamed
+    /// sub-systems match both their label and kernel name.
+    pub fn subsys_id(&self, name: &str) -> Option<u32> {
+        let matches = |subsys: &str| {
+            subsys.eq_ignore_ascii_case(name)
+                || subsys
+                    .strip_prefix("SKB_DROP_REASON_SUBSYS_")
+                    .is_some_and(|s| s.eq_ignore_ascii_case(name))
+        };
+
+        self.subsystems()
+            .find(|(id, subsys)| {
+                matches(subsys)
+                    || self.subsys_kernel_name(*id).is_some_and(matches)
+                    || self
+                        .subsys_labels
+                        .get(id)
+                        .is_some_and(|label| matches(label))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Iterates over the known sub-systems as `(id, name)` pairs, by ascending
+    /// id.
+    pub fn subsystems(&self) -> impl Iterator<Item = (u32, &str)> + '_ {
+        self.subsys
+            .iter()
+            .flat_map(|subsys| subsys.iter())
+            .map(|(id, name)| (*id, name.as_str()))
+    }
+
+    /// Returns a fingerprint of the drop reasons and sub-systems, to detect
+    /// changes between sets of drop reasons. The fingerprint only depends on
+    /// the values and names, identical sets always having the same one.
+    pub fn fingerprint(&self) -> u64 {
+        let mut bytes = Vec::new();
+        let serialize = |bytes: &mut Vec<u8>, map: &BTreeMap<u32, String>| {
+            map.iter().for_each(|(val, name)| {
+                bytes.extend_from_slice(&val.to_le_bytes());
+                bytes.extend_from_slice(name.as_bytes());
+                // Separate names from the next value.
+                bytes.push(0);
+            })
+        };
+
+        serialize(&mut bytes, &self.reasons);
+        // Tell the reasons and sub-systems apart, and a missing sub-system
+        // list from an empty one.
+        bytes.push(0xff);
+        if let Some(subsys) = &self.subsys {
+            bytes.push(1);
+            serialize(&mut bytes, subsys);
+        }
+
+        // 64-bit FNV-1a, which is stable across platforms and releases.
+        bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+            (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Returns the number of drop reasons defined by each sub-system, indexed
+    /// by sub-system id. Only sub-systems defining at least one reason are
+    /// reported.
+    pub fn count_by_subsys(&self) -> BTreeMap<u32, usize> {
+        self.reasons.keys().fold(BTreeMap::new(), |mut count, val| {
+            *count.entry(val >> 16).or_default() += 1;
+            count
+        })
+    }
+
+    /// Returns the ids of the known sub-systems defining no drop reason, eg.
+    /// registered but unused ones, by ascending id. The
+    /// `SKB_DROP_REASON_SUBSYS_NUM` terminator is not reported.
+    pub fn empty_subsys(&self) -> Vec<u32> {
+        let count = self.count_by_subsys();
+        self.subsystems()
+            .map(|(id, _)| id)
+            .filter(|id| {
+                !count.contains_key(id)
+                    && self.subsys_kernel_name(*id) != Some(SKB_DROP_REASON_SUBSYS_NUM_NAME)
+            })
+            .collect()
+    }
+
+    /// Groups the drop reasons by category, being the leading token of their
+    /// name once stripped from its `SKB_DROP_REASON_` (or `SKB_`) prefix, eg.
+    /// `TCP` for `SKB_DROP_REASON_TCP_CSUM`. Values are sorted in ascending
+    /// order within a category.
+    pub fn categories(&self) -> BTreeMap<&str, Vec<u32>> {
+        self.by_value()
+            .fold(BTreeMap::new(), |mut categories, (val, name)| {
+                let name = name
+                    .strip_prefix("SKB_DROP_REASON_")
+                    .or_else(|| name.strip_prefix("SKB_"))
+                    .unwrap_or(name);
+                let category = name.split('_').next().unwrap_or(name);
+                categories
+                    .entry(category)
+                    .or_insert_with(Vec::new)
+                    .push(val);
+                categories
+            })
+    }
+
+    /// Groups the drop reasons by semantic equivalence, eg. `checksum` for the
+    /// checksum failures of the various protocols, using the table of the
+    /// `equivalence` feature. Reasons not known to be equivalent to others
+    /// (all of them without the feature) are their own group, named after
+    /// them. Values are sorted in ascending order within a group.
+    pub fn equivalence_groups(&self) -> BTreeMap<&str, Vec<u32>> {
+        self.by_value()
+            .fold(BTreeMap::new(), |mut groups, (val, name)| {
+                #[cfg(feature = "equivalence")]
+                let name = self
+                    .kernel_name(val)
+                    .and_then(crate::equivalence::group)
+                    .unwrap_or(name);
+                groups.entry(name).or_insert_with(Vec::new).push(val);
+                groups
+            })
+    }
+
+    /// Returns a drop reason given its raw value, if known.
+    pub fn reason(&self, val: u32) -> Option<DropReason> {
+        self.get(val).map(|_| DropReason {
+            value: val,
+            index: val & !SKB_DROP_REASON_SUBSYS_MASK,
+            // Unwrap as the value is known.
+            name: self.kernel_name(val).unwrap().to_string(),
+            subsystem: self.subsys_kernel_name(val >> 16).map(str::to_string),
+            description: self.description(val).map(str::to_string),
+            stability: self.stability(val),
+            is_consume: self.is_consume(val),
+            label: self.label(val).map(str::to_string),
+            source: self.source(val).map(str::to_string),
+            aliases: self.aliases(val).into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    /// Iterates over the drop reasons, by ascending raw value.
+    pub fn iter(&self) -> impl Iterator<Item = DropReason> + '_ {
+        // Unwrap as we're iterating over known values.
+        self.reasons.keys().map(|val| self.reason(*val).unwrap())
+    }
+
+    /// Resolves a raw value, which might not match a known reason.
+    pub fn resolve(&self, val: u32) -> Resolved<'_> {
+        let name = self.get(val);
+        Resolved {
+            value: val,
+            known: name.is_some(),
+            // The sub-system mask is not a real reason but is still named.
+            name: name.or_else(|| {
+                (val == SKB_DROP_REASON_SUBSYS_MASK).then_some(SKB_DROP_REASON_SUBSYS_MASK_NAME)
+            }),
+            subsystem: self.subsys_kernel_name(val >> 16),
+        }
+    }
+
+    /// Decodes a raw value into its sub-system and reason index parts,
+    /// resolving both. Unlike [`DropReasons::resolve`], the sub-system is
+    /// reported even if the value does not match a known reason.
+    pub fn decode(&self, val: u32) -> Decoded {
+        Decoded {
+            value: val,
+            subsystem_id: val >> 16,
+            subsystem: self.subsys_kernel_name(val >> 16).map(str::to_string),
+            index: val & !SKB_DROP_REASON_SUBSYS_MASK,
+            reason: self.get(val).map(str::to_string),
+        }
+    }
+
+    /// Resolves a batch of raw values, eg. the ones of a histogram of drop
+    /// reasons, returning their resolutions in the same order. This is
+    /// equivalent to calling [`DropReasons::resolve`] on each value, names
+    /// being borrowed and not allocated.
+    pub fn resolve_many(&self, values: &[u32]) -> Vec<Resolved<'_>> {
+        values.iter().map(|val| self.resolve(*val)).collect()
+    }
+
+    /// Computes the changes from this set of drop reasons to the `other` one,
+    /// eg. from an older kernel to a newer one.
+    pub fn diff(&self, other: &DropReasons) -> ReasonDiff {
+        let mut diff = ReasonDiff {
+            added: other
+                .iter()
+                .filter(|r| self.get(r.value).is_none())
+                .collect(),
+            removed: self
+                .iter()
+                .filter(|r| other.get(r.value).is_none())
+                .collect(),
+            renamed: self
+                .by_value()
+                .filter_map(|(val, from)| match other.get(val) {
+                    Some(to) if to != from => Some(Renamed {
+                        value: val,
+                        from: from.to_string(),
+                        to: to.to_string(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        };
+        diff.match_aliases();
+        diff
+    }
+
+    /// Iterates over the drop reasons as `(value, name)` pairs.
+    ///
+    /// The ordering is stable and guaranteed: reasons are returned by
+    /// ascending raw value.
+    pub fn by_value(&self) -> impl Iterator<Item = (u32, &str)> + '_ {
+        self.reasons.iter().map(|(val, name)| (*val, name.as_str()))
+    }
+
+    /// Returns the drop reasons as `(value, name)` pairs.
+    ///
+    /// The ordering is stable and guaranteed: reasons are sorted by name
+    /// (byte-wise comparison) and reasons sharing the same name are sorted by
+    /// ascending raw value.
+    pub fn by_name(&self) -> Vec<(u32, &str)> {
+        let mut reasons: Vec<_> = self.by_value().collect();
+        // The input is sorted by value and the sort is stable, so reasons
+        // sharing the same name stay ordered by value.
+        reasons.sort_by(|a, b| a.1.cmp(b.1));
+        reasons
+    }
+
+    /// Formats a reason for pretty printing. If verbose is set, prints the
+    /// subsystem enum variant corresponding to a reason and its description
+    /// (if available). If a reason is not known, try to always print its
+    /// subsystem if we have a match, along with the nearest known reasons in
+    /// it. The sub-system mask is reported as a sentinel.
+    pub fn format_reason(&self, val: u32, verbose: bool, base: Base) -> String {
+        let format = |s: &str, verbose: bool| -> String {
+            let known = self.reasons.contains_key(&val);
+            let mut s = s.to_string();
+            if verbose {
+                if let Some(name) = self.subsystem_for(val) {
+                    let nearest = self
+                        .nearest(val)
+                        .iter()
+                        .map(|(val, name)| format!("{}={name}", base.format(*val)))
+                        .collect::<Vec<_>>();
+                    let index = val & !SKB_DROP_REASON_SUBSYS_MASK;
+                    match known || nearest.is_empty() {
+                        true => s.push_str(&format!(" (sub-system: {name}, index: {index})")),
+                        false => s.push_str(&format!(
+                            " (sub-system: {name}, index: {index}; nearest known: {})",
+                            nearest.join(", ")
+                        )),
+                    }
+                }
+                if let Some(desc) = self.description(val) {
+                    s.push_str(&format!(": {desc}"));
+                }
+                if self.is_consume(val) == Some(true) {
+                    s.push_str(" [consume]");
+                }
+                let aliases = self.aliases(val);
+                if !aliases.is_empty() {
+                    s.push_str(&format!(" [formerly {}]", aliases.join(", ")));
+                }
+            }
+            s
+        };
+
+        match self.reasons.get(&val) {
+            Some(name) => format(name, verbose),
+            None if val == SKB_DROP_REASON_SUBSYS_MASK => {
+                format!("{SKB_DROP_REASON_SUBSYS_MASK_NAME} (sentinel, not a real reason)")
+            }
+            None => format(&format!("Unknown reason {}", base.format(val)), true),
+        }
+    }
+}
+
+/// Returns the members of the kernel enum `name` exactly as found in the BTF
+/// files of `dir`, for inspecting BTF itself: values are not masked nor
+/// merged, nor truncated to 32 bits, and members are kept in their declaration
+/// order. Both 32 and 64-bit enums are supported. The enum can be defined in
+/// more than one BTF file, members are returned as `(BTF file name, value,
+/// name)`.
+pub fn raw_enum<P: AsRef<Path>>(dir: P, name: &str) -> Result<Vec<(String, RawValue, String)>> {
+    let btf = load_dir(dir.as_ref(), None, |_, _| ())?;
+    let types = btf
+        .resolve_types_by_name(name)
+        .or_else(|_| bail!("Could not find enum {name}"))?;
+
+    let mut members = Vec::new();
+    let mut found = false;
+    for (btf, r#type) in types.iter() {
+        match r#type {
+            Type::Enum(r#enum) => {
+                found = true;
+                for member in r#enum.members.iter() {
+                    let val = match r#enum.is_signed() {
+                        true => RawValue::Signed(member.val() as i64),
+                        false => RawValue::Unsigned(member.val() as u32 as u64),
+                    };
+                    members.push((btf.name.clone(), val, btf.resolve_name(member)?));
+                }
+            }
+            Type::Enum64(r#enum) => {
+                found = true;
+                for member in r#enum.members.iter() {
+                    let val = match r#enum.is_signed() {
+                        true => RawValue::Signed(member.val() as i64),
+                        false => RawValue::Unsigned(member.val()),
+                    };
+                    members.push((btf.name.clone(), val, btf.resolve_name(member)?));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !found {
+        bail!("{name} is not an enum");
+    }
+    Ok(members)
+}
+
+/// Returns the drop reasons of the sub-system `subsys` exactly as defined by
+/// its own enum in the BTF files of `dir` (eg. `ovs_drop_reason`), for
+/// debugging a sub-system: unlike [`DropReasons::from_dir`], reasons reusing
+/// the value of another one are kept. Sub-systems are matched by name as in
+/// [`DropReasons::subsys_id`].
+pub fn subsys_reasons<P: AsRef<Path>>(dir: P, subsys: &str) -> Result<BTreeMap<u32, String>> {
+    let btf = load_dir(dir.as_ref(), None, |_, _| ())?;
+    // Members not fitting in 32 bits are reported by parse_enum.
+    let mut out_of_range = Vec::new();
+    let subsystems = match parse_enum(&btf, "skb_drop_reason_subsys", None, &mut out_of_range)? {
+        Some(subsystems) => DropReasons::new(BTreeMap::new(), Some(subsystems)),
+        None => bail!("Sub-systems are not supported by the kernel"),
+    };
+    let id = match subsystems.subsys_id(subsys) {
+        Some(id) => id,
+        None => bail!("Unknown sub-system {subsys}"),
+    };
+
+    let mut reasons = BTreeMap::new();
+    for r#enum in NON_CORE_DROP_REASONS {
+        if let Some(members) = parse_enum(&btf, r#enum, None, &mut out_of_range)? {
+            reasons.extend(members.into_iter().filter(|(val, _)| val >> 16 == id));
+        }
+    }
+
+    if reasons.is_empty() {
+        bail!("Could not find the drop reasons of sub-system {subsys}");
+    }
+    Ok(reasons)
+}
+
+/// Returns the members of the kernel enum `name` (eg. `ovs_drop_reason`) found
+/// in the BTF files of `dir`, for resolving values against a single enum:
+/// unlike [`DropReasons::from_dir`], they are not merged with the other enums.
+/// If the enum is defined in more than one BTF file, the definition is
+/// selected as when parsing the drop reasons.
+pub fn enum_reasons<P: AsRef<Path>>(dir: P, name: &str) -> Result<BTreeMap<u32, String>> {
+    let btf = load_dir(dir.as_ref(), None, |_, _| ())?;
+    // Members not fitting in 32 bits are reported by parse_enum.
+    match parse_enum(&btf, name, None, &mut Vec::new())? {
+        Some(members) => Ok(members),
+        None => bail!("Could not find enum {name}"),
+    }
+}
+
+// Parses the core drop reasons, without the sub-system mask, preferring the
+// definition of the given BTF file if set (see parse_enum). The name of the
+// removed mask member is returned along the reasons, if found.
+fn parse_core(
+    btf: &BtfCollection,
+    prefer: Option<&str>,
+    out_of_range: &mut Vec<String>,
+) -> Result<(BTreeMap<u32, String>, Option<String>)> {
+    // If not found, the kernel doesn't support drop reasons.
+    let mut reasons = match parse_enum(btf, "skb_drop_reason", prefer, out_of_range) {
+        Ok(Some(reasons)) => reasons,
+        Ok(None) => bail!("Drop reasons are not supported by this kernel"),
+        Err(e) => bail!(e),
+    };
+
+    // Special case the drop reason mask.
+    let mask = reasons.remove(&SKB_DROP_REASON_SUBSYS_MASK);
+    Ok((reasons, mask))
+}
+
+// Parses the `{ val, "name" }` pairs of the first __print_symbolic() of a
+// tracefs event print format into an ordered BTreeMap of (val <> name).
+fn parse_print_symbolic(format: &str) -> Result<BTreeMap<u32, String>> {
+    let symbols = match format.split_once("__print_symbolic(") {
+        Some((_, symbols)) => symbols,
+        None => bail!("no symbolic values found"),
+    };
+
+    let mut values = BTreeMap::new();
+    // The first chunk is the field being printed.
+    for symbol in symbols.split('{').skip(1) {
+        let (val, name) = match symbol.split_once('}').and_then(|(s, _)| s.split_once(',')) {
+            Some(symbol) => symbol,
+            None => bail!("invalid symbol {symbol}"),
+        };
+
+        let val = val.trim();
+        let val = match val.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => val.parse(),
+        }
+        .or_else(|_| bail!("invalid value {val}"))?;
+        values.insert(val, name.trim().trim_matches('"').to_string());
+    }
+
+    if values.is_empty() {
+        bail!("no symbolic values found");
+    }
+    Ok(values)
+}
+
+// Parses a kernel enum into an ordered BTreeMap of (val <> name).
+//
+// The enum can be defined in more than one BTF file (eg. a module re-declaring
+// it). The definition is then selected deterministically, regardless of the
+// order the BTF files were loaded: the one of the `prefer` BTF file is
+// preferred if set, then the vmlinux one, then the one with the most members,
+// then the one from the BTF file with the lowest name. A warning is emitted if
+// the definitions disagree.
+//
+// Members of 64-bit enums whose value does not fit in 32 bits are skipped
+// rather than truncated: a warning is emitted and they are added, as
+// ENUM::MEMBER, to `out_of_range`.
+fn parse_enum(
+    btf: &BtfCollection,
+    name: &str,
+    prefer: Option<&str>,
+    out_of_range: &mut Vec<String>,
+) -> Result<Option<BTreeMap<u32, String>>> {
+    let types = match btf.resolve_types_by_name(name) {
+        Ok(types) => types,
+        Err(_) => return Ok(None),
+    };
+
+    let mut defs = Vec::new();
+    for (btf, r#type) in types.iter() {
+        let mut values = BTreeMap::new();
+        match r#type {
+            // Values of 32-bit enums always fit, only their signedness can
+            // differ.
+            Type::Enum(r#enum) => {
+                for member in r#enum.members.iter() {
+                    values.insert(member.val() as u32, btf.resolve_name(member)?);
+                }
+            }
+            Type::Enum64(r#enum) => {
+                for member in r#enum.members.iter() {
+                    let member_name = btf.resolve_name(member)?;
+                    match u32::try_from(member.val()) {
+                        Ok(val) => {
+                            values.insert(val, member_name);
+                        }
+                        Err(_) => {
+                            log::warn!(
+                                "skipping {name}::{member_name} (in {}), its value {:#x} does not fit in 32 bits",
+                                btf.name,
+                                member.val()
+                            );
+                            let member = format!("{name}::{member_name}");
+                            if !out_of_range.contains(&member) {
+                                out_of_range.push(member);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => continue,
+        }
+        defs.push((btf.name.as_str(), values));
+    }
+
+    defs.sort_by(|(a, a_values), (b, b_values)| {
+        (Some(*b) == prefer)
+            .cmp(&(Some(*a) == prefer))
+            .then((*b == "vmlinux").cmp(&(*a == "vmlinux")))
+            .then(b_values.len().cmp(&a_values.len()))
+            .then(a.cmp(b))
+    });
+
+    let mut defs = defs.into_iter();
+    let (file, values) = match defs.next() {
+        Some(def) => def,
+        None => return Ok(None),
+    };
+
+    log::debug!("using the definition of enum {name} from {file}");
+    for (other, _) in defs.filter(|(_, other)| *other != values) {
+        log::warn!(
+            "definitions of enum {name} differ in {file} and {other}, using the one from {file}"
+        );
+    }
+
+    Ok(Some(values))
+}
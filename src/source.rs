@@ -0,0 +1,147 @@
+//! Sources of BTF files to parse the drop reasons from (see
+//! [`DropReasons::from_source`](crate::DropReasons::from_source)).
+//!
+//! Library users can implement [`BtfSource`] to provide BTF from elsewhere,
+//! eg. from a database.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use btf_rs::utils::BtfCollection;
+
+/// Source of BTF files.
+pub trait BtfSource {
+    /// Loads the BTF files, for the drop reasons to be parsed from them.
+    fn load(&self) -> Result<BtfCollection>;
+}
+
+/// BTF files found in a directory (eg. `/sys/kernel/btf`): the base `vmlinux`
+/// one and the module ones.
+pub struct Dir {
+    /// Directory holding the BTF files.
+    pub path: PathBuf,
+    /// If set, only the module BTF files whose name matches one of these glob
+    /// patterns (supporting `*` and `?`) are loaded.
+    pub patterns: Option<Vec<String>>,
+}
+
+impl Dir {
+    /// Builds a source loading all the BTF files found in `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            patterns: None,
+        }
+    }
+}
+
+impl BtfSource for Dir {
+    fn load(&self) -> Result<BtfCollection> {
+        match &self.patterns {
+            Some(patterns) => load_dir(&self.path, Some(patterns), |_, _| ()),
+            None => BtfCollection::from_dir(&self.path)
+                .or_else(|e| bail!("Could not parse BTF files: {e}")),
+        }
+    }
+}
+
+/// A single base BTF file, eg. a `vmlinux` one. Only its drop reasons are
+/// known, not the ones of modules.
+pub struct File {
+    /// Path of the BTF file.
+    pub path: PathBuf,
+}
+
+impl File {
+    /// Builds a source loading the BTF file at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl BtfSource for File {
+    fn load(&self) -> Result<BtfCollection> {
+        BtfCollection::from_file(&self.path)
+            .or_else(|e| bail!("Could not parse BTF file {}: {e}", self.path.display()))
+    }
+}
+
+// Loads the base vmlinux BTF file found in dir and the module ones matching
+// one of the patterns, if set, reporting the progress (see
+// DropReasons::from_dir_with_progress).
+pub(crate) fn load_dir<F: FnMut(usize, usize)>(
+    dir: &Path,
+    patterns: Option<&[String]>,
+    mut progress: F,
+) -> Result<BtfCollection> {
+    let mut btf = BtfCollection::from_file(dir.join("vmlinux"))
+        .or_else(|e| bail!("Could not parse BTF files: {e}"))?;
+
+    let mut files = fs::read_dir(dir)
+        .or_else(|e| bail!("Could not read {}: {e}", dir.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    files.retain(|file| {
+        file.file_name()
+            .map(|name| name.to_string_lossy())
+            .is_some_and(|name| {
+                name != "vmlinux"
+                    && match patterns {
+                        Some(patterns) => patterns.iter().any(|p| glob_match(p, &name)),
+                        None => true,
+                    }
+            })
+    });
+    files.sort();
+
+    // Account for vmlinux.
+    let total = files.len() + 1;
+    progress(1, total);
+
+    for (i, file) in files.iter().enumerate() {
+        btf.add_split_btf_from_file(file)
+            .or_else(|e| bail!("Could not parse BTF file {}: {e}", file.display()))?;
+        progress(i + 2, total);
+    }
+
+    log::debug!("loaded {total} BTF files from {}", dir.display());
+    Ok(btf)
+}
+
+// Matches a name against a glob pattern, supporting `*` (any sequence of
+// characters) and `?` (any single character).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<_>, Vec<_>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern and of the name when it was
+    // encountered, to backtrack on mismatches.
+    let mut star = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
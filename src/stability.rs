@@ -0,0 +1,46 @@
+//! Stability of the drop reasons, ie. whether tooling can rely on them.
+//!
+//! The kernel does not document drop reasons as UAPI in general, so only the
+//! reasons whose value is relied upon by the kernel itself are marked as
+//! stable. Other core reasons are left unknown rather than guessed.
+
+use serde::Serialize;
+
+/// Stability of a drop reason.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Stability {
+    /// The reason and its value are part of the stable tracepoint interface.
+    Uapi,
+    /// The reason is internal and can change freely between kernel versions.
+    Internal,
+}
+
+impl Stability {
+    /// Returns the name of the stability level, as used in the outputs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stability::Uapi => "uapi",
+            Stability::Internal => "internal",
+        }
+    }
+}
+
+// Core reasons with a fixed value: SKB_NOT_DROPPED_YET (0) is the sentinel
+// used by the kernel for non-dropped packets and SKB_CONSUMED (1) is checked
+// by the kfree_skb tracepoint users to tell consumed packets from drops.
+const UAPI: &[&str] = &["SKB_CONSUMED", "SKB_NOT_DROPPED_YET"];
+
+/// Returns the stability of a drop reason given its raw value and name, if
+/// known. Reasons from non-core sub-systems are private to their sub-system
+/// and always internal.
+pub fn get(val: u32, name: &str) -> Option<Stability> {
+    if val >> 16 != 0 {
+        Some(Stability::Internal)
+    } else if UAPI.contains(&name) {
+        Some(Stability::Uapi)
+    } else {
+        None
+    }
+}
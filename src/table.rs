@@ -0,0 +1,53 @@
+//! Drop reasons translation using static tables, eg. generated by drdump. This
+//! does not depend on BTF nor the standard library (only `alloc` is required).
+
+use alloc::{format, string::String};
+
+/// Translation table of drop reasons and sub-systems.
+pub struct Table<'a> {
+    reasons: &'a [(u32, &'a str)],
+    subsys: &'a [(u32, &'a str)],
+}
+
+impl<'a> Table<'a> {
+    /// Builds a translation table given `(value, name)` drop reasons and
+    /// `(id, name)` sub-systems. Both must be sorted by ascending value (or
+    /// id).
+    pub const fn new(reasons: &'a [(u32, &'a str)], subsys: &'a [(u32, &'a str)]) -> Self {
+        Self { reasons, subsys }
+    }
+
+    /// Returns the name of a drop reason given its raw value, if known.
+    pub fn get(&self, val: u32) -> Option<&'a str> {
+        lookup(self.reasons, val)
+    }
+
+    /// Returns the name of the sub-system a raw value belongs to, if known.
+    pub fn subsys_name(&self, val: u32) -> Option<&'a str> {
+        lookup(self.subsys, val >> 16)
+    }
+
+    /// Formats a reason for pretty printing: its name, followed by its
+    /// sub-system if verbose is set or if the reason is not known. Unlike
+    /// `DropReasons::format_reason`, the reason index, nearest known reasons
+    /// and descriptions are not reported.
+    pub fn format_reason(&self, val: u32, verbose: bool) -> String {
+        let (name, verbose) = match self.get(val) {
+            Some(name) => (String::from(name), verbose),
+            None => (format!("Unknown reason {val}"), true),
+        };
+
+        match self.subsys_name(val) {
+            Some(subsys) if verbose => format!("{name} (sub-system: {subsys})"),
+            _ => name,
+        }
+    }
+}
+
+// Looks up a value in a sorted (value, name) table.
+fn lookup<'a>(table: &[(u32, &'a str)], val: u32) -> Option<&'a str> {
+    table
+        .binary_search_by_key(&val, |(v, _)| *v)
+        .ok()
+        .map(|i| table[i].1)
+}
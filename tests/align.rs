@@ -0,0 +1,34 @@
+use std::collections::BTreeMap;
+
+use drdump::{format, DropReasons};
+
+#[test]
+fn raw_aligned_names() {
+    let reasons = DropReasons::new(
+        BTreeMap::from([
+            (0, "SKB_NOT_DROPPED_YET".to_string()),
+            (1, "SKB_CONSUMED".to_string()),
+            (2, "SKB_DROP_REASON_NOT_SPECIFIED".to_string()),
+            (0x10001, "RX_DROP_U_REPLAY".to_string()),
+        ]),
+        Some(BTreeMap::from([
+            (0, "SKB_DROP_REASON_SUBSYS_CORE".to_string()),
+            (1, "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE".to_string()),
+        ])),
+    );
+
+    let opts = format::Opts {
+        verbose: true,
+        align_names: true,
+        ..Default::default()
+    };
+    let raw = format::raw(&reasons, &opts);
+    let columns: Vec<_> = raw
+        .lines()
+        .map(|line| line.find(" (sub-system: ").unwrap())
+        .collect();
+
+    assert_eq!(columns.len(), 4);
+    assert!(columns.iter().all(|c| *c == columns[0]));
+    assert_eq!(columns[0], "65537 = SKB_DROP_REASON_NOT_SPECIFIED".len());
+}
@@ -0,0 +1,110 @@
+mod fixtures;
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::{self, Command, Output},
+};
+
+// Header declaring the core drop reasons of fixtures::kernel.
+const HEADER: &str = "enum skb_drop_reason {
+	/* Not dropped. */
+	SKB_NOT_DROPPED_YET = 0,
+	SKB_CONSUMED,
+	SKB_DROP_REASON_NOT_SPECIFIED,
+	SKB_DROP_REASON_NO_SOCKET,
+	SKB_DROP_REASON_MAX,
+	SKB_DROP_REASON_SUBSYS_MASK = 0xffff0000,
+};
+";
+
+// Runs drdump with the given arguments.
+fn drdump(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_drdump"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// File written in the temporary directory, removed on drop.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn new(name: &str, content: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("drdump-{}-{name}", process::id()));
+        fs::write(&path, content).unwrap();
+        Self { path }
+    }
+
+    fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Returns the --btf argument pointing to the fixture.
+fn btf_dir(fixture: &fixtures::Fixture) -> &str {
+    fixture.dir.to_str().unwrap()
+}
+
+#[test]
+fn validate_header() {
+    let fixture = fixtures::kernel().write("cli_validate_header");
+    let header = TempFile::new("cli_validate_header.h", HEADER);
+
+    let output = drdump(&["--btf", btf_dir(&fixture), "validate-header", header.path()]);
+    assert!(output.status.success());
+
+    // The sub-system mask is not compared.
+    let output = drdump(&[
+        "--btf",
+        btf_dir(&fixture),
+        "--keep-mask",
+        "validate-header",
+        header.path(),
+    ]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn validate_header_mismatch() {
+    let fixture = fixtures::kernel().write("cli_validate_header_mismatch");
+    let header = TempFile::new(
+        "cli_validate_header_mismatch.h",
+        &HEADER.replace("SKB_DROP_REASON_NO_SOCKET", "SKB_DROP_REASON_NO_ROUTE"),
+    );
+
+    let output = drdump(&["--btf", btf_dir(&fixture), "validate-header", header.path()]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("header only: SKB_DROP_REASON_NO_ROUTE"));
+    assert!(stdout.contains("BTF only: SKB_DROP_REASON_NO_SOCKET"));
+}
+
+// Names printed by the kernel can't be compared with the header ones.
+#[test]
+fn validate_header_printk() {
+    let format = TempFile::new(
+        "cli_validate_header_printk.format",
+        "print fmt: \"reason: %s\", __print_symbolic(REC->reason, { 0, \"NOT_DROPPED_YET\" }, { 1, \"CONSUMED\" })",
+    );
+    let header = TempFile::new("cli_validate_header_printk.h", HEADER);
+
+    let output = drdump(&[
+        "--from-printk-format",
+        format.path(),
+        "validate-header",
+        header.path(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("requires the drop reasons to be read from BTF"));
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use drdump::{DropReasons, Renamed};
+
+fn reasons(reasons: &[(u32, &str)]) -> DropReasons {
+    DropReasons::new(
+        reasons
+            .iter()
+            .map(|(val, name)| (*val, name.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        None,
+    )
+}
+
+fn old() -> DropReasons {
+    reasons(&[
+        (0, "SKB_NOT_DROPPED_YET"),
+        (2, "SKB_DROP_REASON_NOT_SPECIFIED"),
+        (3, "SKB_DROP_REASON_NO_SOCKET"),
+    ])
+}
+
+#[test]
+fn same() {
+    let diff = old().diff(&old());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn added() {
+    let new = reasons(&[
+        (0, "SKB_NOT_DROPPED_YET"),
+        (2, "SKB_DROP_REASON_NOT_SPECIFIED"),
+        (3, "SKB_DROP_REASON_NO_SOCKET"),
+        (4, "SKB_DROP_REASON_PKT_TOO_SMALL"),
+        (0x30001, "OVS_DROP_LAST_ACTION"),
+    ]);
+
+    let diff = old().diff(&new);
+    let added: Vec<_> = diff
+        .added
+        .iter()
+        .map(|r| (r.value, r.name.as_str()))
+        .collect();
+    assert_eq!(
+        added,
+        vec![
+            (4, "SKB_DROP_REASON_PKT_TOO_SMALL"),
+            (0x30001, "OVS_DROP_LAST_ACTION")
+        ]
+    );
+    assert!(diff.removed.is_empty());
+    assert!(diff.renamed.is_empty());
+}
+
+#[test]
+fn removed() {
+    let new = reasons(&[(0, "SKB_NOT_DROPPED_YET"), (3, "SKB_DROP_REASON_NO_SOCKET")]);
+
+    let diff = old().diff(&new);
+    assert!(diff.added.is_empty());
+    let removed: Vec<_> = diff
+        .removed
+        .iter()
+        .map(|r| (r.value, r.name.as_str()))
+        .collect();
+    assert_eq!(removed, vec![(2, "SKB_DROP_REASON_NOT_SPECIFIED")]);
+    assert!(diff.renamed.is_empty());
+}
+
+#[test]
+fn renamed() {
+    let new = reasons(&[
+        (0, "SKB_NOT_DROPPED_YET"),
+        (2, "SKB_DROP_REASON_NOT_SPECIFIED"),
+        (3, "SKB_DROP_REASON_NO_SK"),
+    ]);
+
+    let diff = old().diff(&new);
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(
+        diff.renamed,
+        vec![Renamed {
+            value: 3,
+            from: "SKB_DROP_REASON_NO_SOCKET".to_string(),
+            to: "SKB_DROP_REASON_NO_SK".to_string(),
+        }]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "~ 3 = SKB_DROP_REASON_NO_SOCKET -> SKB_DROP_REASON_NO_SK\n"
+    );
+}
+
+#[test]
+fn labels() {
+    let mut labelled = old();
+    assert!(labelled
+        .rename(BTreeMap::from([(3, "no socket".to_string())]))
+        .is_empty());
+
+    // Labels are only for display and don't affect comparisons.
+    assert!(old().diff(&labelled).is_empty());
+    assert_eq!(old().fingerprint(), labelled.fingerprint());
+    assert_eq!(labelled.get(3), Some("SKB_DROP_REASON_NO_SOCKET"));
+    assert_eq!(labelled.label(3), Some("no socket"));
+
+    let view = labelled.labelled();
+    assert_eq!(view.get(3), Some("no socket"));
+    assert_eq!(view.value_of("SKB_DROP_REASON_NO_SOCKET"), Some(3));
+    assert_eq!(view.reason(3).unwrap().name, "SKB_DROP_REASON_NO_SOCKET");
+}
@@ -0,0 +1,170 @@
+//! Synthetic BTF fixtures, to test parsing drop reasons without a kernel.
+
+// Not all helpers are used by all the test files including the module.
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf, process};
+
+// BTF header magic, version and (fixed) length.
+const BTF_MAGIC: u16 = 0xeb9f;
+const BTF_VERSION: u8 = 1;
+const BTF_HDR_LEN: u32 = 24;
+// BTF kinds of enums with 32-bit and 64-bit values.
+const BTF_KIND_ENUM: u32 = 6;
+const BTF_KIND_ENUM64: u32 = 19;
+
+/// BTF blob builder, only supporting (32-bit and 64-bit) enums.
+pub struct Btf {
+    types: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl Btf {
+    pub fn new() -> Self {
+        Self {
+            types: Vec::new(),
+            // The first string is always the empty one.
+            strings: vec![0],
+        }
+    }
+
+    /// Adds an enum type made of `(name, value)` members.
+    pub fn add_enum(&mut self, name: &str, members: &[(&str, u32)]) -> &mut Self {
+        let name_off = self.add_string(name);
+        let info = (BTF_KIND_ENUM << 24) | members.len() as u32;
+
+        // struct btf_type, with the size of the enum.
+        self.push(name_off);
+        self.push(info);
+        self.push(4);
+
+        // struct btf_enum, for each member.
+        for (name, val) in members.iter() {
+            let name_off = self.add_string(name);
+            self.push(name_off);
+            self.push(*val);
+        }
+
+        self
+    }
+
+    /// Adds a 64-bit enum type made of `(name, value)` members.
+    pub fn add_enum64(&mut self, name: &str, members: &[(&str, u64)]) -> &mut Self {
+        let name_off = self.add_string(name);
+        let info = (BTF_KIND_ENUM64 << 24) | members.len() as u32;
+
+        // struct btf_type, with the size of the enum.
+        self.push(name_off);
+        self.push(info);
+        self.push(8);
+
+        // struct btf_enum64, for each member.
+        for (name, val) in members.iter() {
+            let name_off = self.add_string(name);
+            self.push(name_off);
+            self.push(*val as u32);
+            self.push((val >> 32) as u32);
+        }
+
+        self
+    }
+
+    /// Adds the `skb_drop_reason_subsys` enum for the given sub-systems, their
+    /// id being their position.
+    pub fn add_subsys(&mut self, subsys: &[&str]) -> &mut Self {
+        let members: Vec<_> = subsys
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, id as u32))
+            .collect();
+        self.add_enum("skb_drop_reason_subsys", &members)
+    }
+
+    /// Returns the raw BTF blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&BTF_MAGIC.to_le_bytes());
+        bytes.push(BTF_VERSION);
+        bytes.push(0); // flags
+        bytes.extend_from_slice(&BTF_HDR_LEN.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // type_off
+        bytes.extend_from_slice(&(self.types.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.types.len() as u32).to_le_bytes()); // str_off
+        bytes.extend_from_slice(&(self.strings.len() as u32).to_le_bytes());
+
+        bytes.extend_from_slice(&self.types);
+        bytes.extend_from_slice(&self.strings);
+        bytes
+    }
+
+    /// Writes the BTF blob as the `vmlinux` file of a new temporary
+    /// directory, removed when the returned fixture is dropped.
+    pub fn write(&self, name: &str) -> Fixture {
+        let dir = std::env::temp_dir().join(format!("drdump-{}-{name}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("vmlinux"), self.to_bytes()).unwrap();
+        Fixture { dir }
+    }
+
+    fn add_string(&mut self, s: &str) -> u32 {
+        let off = self.strings.len() as u32;
+        self.strings.extend_from_slice(s.as_bytes());
+        self.strings.push(0);
+        off
+    }
+
+    fn push(&mut self, val: u32) {
+        self.types.extend_from_slice(&val.to_le_bytes());
+    }
+}
+
+/// Directory holding BTF files, removed on drop.
+pub struct Fixture {
+    pub dir: PathBuf,
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Builds a BTF blob looking like the one of a kernel with drop reasons from
+/// the core and two sub-systems, one of them (ovs) reusing a core value.
+pub fn kernel() -> Btf {
+    let mut btf = Btf::new();
+    btf.add_enum(
+        "skb_drop_reason",
+        &[
+            ("SKB_NOT_DROPPED_YET", 0),
+            ("SKB_CONSUMED", 1),
+            ("SKB_DROP_REASON_NOT_SPECIFIED", 2),
+            ("SKB_DROP_REASON_NO_SOCKET", 3),
+            ("SKB_DROP_REASON_MAX", 4),
+            ("SKB_DROP_REASON_SUBSYS_MASK", 0xffff0000),
+        ],
+    )
+    .add_enum(
+        "mac80211_drop_reason",
+        &[
+            ("RX_DROP_U_MIC_FAIL", 0x10001),
+            ("RX_DROP_U_REPLAY", 0x10002),
+        ],
+    )
+    .add_enum(
+        "ovs_drop_reason",
+        &[
+            ("OVS_DROP_REUSED_CONSUMED", 1),
+            ("OVS_DROP_LAST_ACTION", 0x30001),
+        ],
+    )
+    .add_subsys(&[
+        "SKB_DROP_REASON_SUBSYS_CORE",
+        "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE",
+        "SKB_DROP_REASON_SUBSYS_MAC80211_MONITOR",
+        "SKB_DROP_REASON_SUBSYS_OPENVSWITCH",
+        "SKB_DROP_REASON_SUBSYS_NUM",
+    ]);
+    btf
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use drdump::{format, DropReasons};
+
+fn reasons() -> DropReasons {
+    DropReasons::new(
+        BTreeMap::from([
+            (0, "SKB_NOT_DROPPED_YET".to_string()),
+            (1, "SKB_CONSUMED".to_string()),
+            (2, "SKB_DROP_REASON_NOT_SPECIFIED".to_string()),
+            (0x10001, "RX_DROP_U_REPLAY".to_string()),
+        ]),
+        Some(BTreeMap::from([
+            (0, "SKB_DROP_REASON_SUBSYS_CORE".to_string()),
+            (1, "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE".to_string()),
+        ])),
+    )
+}
+
+// Descriptions are part of the verbose output.
+#[cfg(not(feature = "descriptions"))]
+#[test]
+fn raw_deterministic() {
+    assert_eq!(
+        format::raw(
+            &reasons(),
+            &format::Opts {
+                verbose: true,
+                deterministic: true,
+                ..Default::default()
+            }
+        ),
+        include_str!("golden/raw.txt").trim_end()
+    );
+}
+
+#[test]
+fn bpftrace() {
+    assert_eq!(
+        format::bpftrace(&reasons(), &Default::default()),
+        include_str!("golden/drop.bt").trim_end()
+    );
+}
+
+#[test]
+fn stap() {
+    assert_eq!(
+        format::stap(&reasons(), &Default::default()),
+        include_str!("golden/drop.stp").trim_end()
+    );
+}
+
+// Scripts using all the options, for the generated text to be locked too.
+fn all_options() -> format::Opts {
+    format::Opts {
+        min_count: Some(10),
+        tracepoints: vec!["kfree_skb".to_string(), "consume_skb".to_string()],
+        wrap_width: Some(40),
+        duration: Some(60),
+        annotate: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn bpftrace_all_options() {
+    assert_eq!(
+        format::bpftrace(&reasons(), &all_options()),
+        include_str!("golden/drop-all-options.bt").trim_end()
+    );
+}
+
+#[test]
+fn stap_all_options() {
+    assert_eq!(
+        format::stap(&reasons(), &all_options()),
+        include_str!("golden/drop-all-options.stp").trim_end()
+    );
+}
+
+// Generated scripts must not embed anything depending on the generation
+// itself (eg. a timestamp) for them to be reproducible.
+#[test]
+fn scripts_reproducible() {
+    let (values, subsys) = reasons().into_parts();
+    // Same reasons, inserted in reverse order.
+    let reversed = DropReasons::new(values.into_iter().rev().collect(), subsys);
+
+    assert_eq!(
+        format::bpftrace(&reasons(), &Default::default()),
+        format::bpftrace(&reversed, &Default::default())
+    );
+    assert_eq!(
+        format::stap(&reasons(), &Default::default()),
+        format::stap(&reversed, &Default::default())
+    );
+}
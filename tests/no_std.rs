@@ -0,0 +1,19 @@
+// Makes sure the table module can be used from a no_std crate.
+#![no_std]
+
+use drdump::table::Table;
+
+static TABLE: Table = Table::new(
+    &[(0, "SKB_NOT_DROPPED_YET"), (0x10002, "RX_DROP_U_REPLAY")],
+    &[(1, "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE")],
+);
+
+#[test]
+fn lookup() {
+    assert_eq!(TABLE.get(0x10002), Some("RX_DROP_U_REPLAY"));
+    assert_eq!(TABLE.get(1), None);
+    assert_eq!(
+        TABLE.format_reason(0x10003, false),
+        "Unknown reason 65539 (sub-system: SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE)"
+    );
+}
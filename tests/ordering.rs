@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use drdump::DropReasons;
+
+fn reasons() -> DropReasons {
+    let reasons = BTreeMap::from([
+        (2, "SKB_DROP_REASON_NOT_SPECIFIED".to_string()),
+        (0, "SKB_NOT_DROPPED_YET".to_string()),
+        (0x30001, "OVS_DROP_LAST_ACTION".to_string()),
+        (1, "SKB_CONSUMED".to_string()),
+        (0x10001, "RX_DROP_U_REPLAY".to_string()),
+    ]);
+    DropReasons::new(reasons, None)
+}
+
+#[test]
+fn by_value() {
+    let values: Vec<_> = reasons().by_value().map(|(val, _)| val).collect();
+    assert_eq!(values, vec![0, 1, 2, 0x10001, 0x30001]);
+}
+
+#[test]
+fn by_name() {
+    let reasons = reasons();
+    let names: Vec<_> = reasons
+        .by_name()
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "OVS_DROP_LAST_ACTION",
+            "RX_DROP_U_REPLAY",
+            "SKB_CONSUMED",
+            "SKB_DROP_REASON_NOT_SPECIFIED",
+            "SKB_NOT_DROPPED_YET",
+        ]
+    );
+}
+
+#[test]
+fn by_name_duplicates() {
+    let reasons = DropReasons::new(
+        BTreeMap::from([
+            (0x30001, "SKB_CONSUMED".to_string()),
+            (2, "SKB_DROP_REASON_NOT_SPECIFIED".to_string()),
+            (1, "SKB_CONSUMED".to_string()),
+        ]),
+        None,
+    );
+    assert_eq!(
+        reasons.by_name(),
+        vec![
+            (1, "SKB_CONSUMED"),
+            (0x30001, "SKB_CONSUMED"),
+            (2, "SKB_DROP_REASON_NOT_SPECIFIED"),
+        ]
+    );
+}
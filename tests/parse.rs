@@ -0,0 +1,138 @@
+mod fixtures;
+
+use drdump::{Base, DropReasons};
+
+#[test]
+fn core_and_subsys() {
+    let fixture = fixtures::kernel().write("core_and_subsys");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(reasons.get(0), Some("SKB_NOT_DROPPED_YET"));
+    assert_eq!(reasons.get(3), Some("SKB_DROP_REASON_NO_SOCKET"));
+    assert_eq!(reasons.get(0x10002), Some("RX_DROP_U_REPLAY"));
+    assert_eq!(reasons.get(0x30001), Some("OVS_DROP_LAST_ACTION"));
+    assert_eq!(
+        reasons.subsys_name(3),
+        Some("SKB_DROP_REASON_SUBSYS_OPENVSWITCH")
+    );
+    assert!(!reasons.unknown_subsys());
+}
+
+#[test]
+fn from_bytes() {
+    let bytes = fixtures::kernel().to_bytes();
+    let reasons = DropReasons::from_bytes(&bytes).unwrap();
+
+    assert_eq!(reasons.get(3), Some("SKB_DROP_REASON_NO_SOCKET"));
+    assert_eq!(reasons.get(0x10002), Some("RX_DROP_U_REPLAY"));
+    assert_eq!(reasons.source(0x30001), Some("ovs_drop_reason"));
+    assert_eq!(reasons.get(0xffff0000), None);
+    assert!(DropReasons::from_bytes(&bytes[..8]).is_err());
+}
+
+#[test]
+fn subsys_mask_removed() {
+    let fixture = fixtures::kernel().write("subsys_mask_removed");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(reasons.get(0xffff0000), None);
+    assert_eq!(reasons.value_of("SKB_DROP_REASON_SUBSYS_MASK"), None);
+}
+
+#[test]
+fn core_not_overwritten() {
+    let fixture = fixtures::kernel().write("core_not_overwritten");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(reasons.get(1), Some("SKB_CONSUMED"));
+    assert_eq!(reasons.value_of("OVS_DROP_REUSED_CONSUMED"), None);
+    assert_eq!(
+        reasons.duplicates(),
+        &[(
+            1,
+            "SKB_CONSUMED".to_string(),
+            "OVS_DROP_REUSED_CONSUMED".to_string()
+        )]
+    );
+}
+
+#[test]
+fn subsystem_for() {
+    let fixture = fixtures::kernel().write("subsystem_for");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(
+        reasons.subsystem_for(0),
+        Some("SKB_DROP_REASON_SUBSYS_CORE")
+    );
+    assert_eq!(
+        reasons.subsystem_for(0x10002),
+        Some("SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE")
+    );
+    // Unknown reasons are still mapped to their sub-system.
+    assert_eq!(
+        reasons.subsystem_for(0x30009),
+        Some("SKB_DROP_REASON_SUBSYS_OPENVSWITCH")
+    );
+    assert_eq!(reasons.subsystem_for(0x70001), None);
+}
+
+#[test]
+fn out_of_range() {
+    let mut btf = fixtures::Btf::new();
+    btf.add_enum(
+        "skb_drop_reason",
+        &[
+            ("SKB_NOT_DROPPED_YET", 0),
+            ("SKB_DROP_REASON_NOT_SPECIFIED", 2),
+        ],
+    )
+    .add_enum64(
+        "ovs_drop_reason",
+        &[
+            ("OVS_DROP_LAST_ACTION", 0x30001),
+            ("OVS_DROP_TOO_LARGE", 0x1_0003_0002),
+        ],
+    )
+    .add_subsys(&[
+        "SKB_DROP_REASON_SUBSYS_CORE",
+        "SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE",
+        "SKB_DROP_REASON_SUBSYS_MAC80211_MONITOR",
+        "SKB_DROP_REASON_SUBSYS_OPENVSWITCH",
+    ]);
+    let fixture = btf.write("out_of_range");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(reasons.get(0x30001), Some("OVS_DROP_LAST_ACTION"));
+    // Not truncated to 0x30002.
+    assert_eq!(reasons.get(0x30002), None);
+    assert_eq!(reasons.value_of("OVS_DROP_TOO_LARGE"), None);
+    assert_eq!(
+        reasons.out_of_range(),
+        &["ovs_drop_reason::OVS_DROP_TOO_LARGE".to_string()]
+    );
+}
+
+#[test]
+fn not_supported() {
+    let mut btf = fixtures::Btf::new();
+    btf.add_subsys(&["SKB_DROP_REASON_SUBSYS_CORE"]);
+    let fixture = btf.write("not_supported");
+
+    assert!(DropReasons::from_dir(&fixture.dir).is_err());
+}
+
+#[test]
+fn format_reason() {
+    let fixture = fixtures::kernel().write("format_reason");
+    let reasons = DropReasons::from_dir(&fixture.dir).unwrap();
+
+    assert_eq!(
+        reasons.format_reason(0x10002, true, Base::Dec),
+        "RX_DROP_U_REPLAY (sub-system: SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE, index: 2)"
+    );
+    assert_eq!(
+        reasons.format_reason(0x10009, false, Base::Hex),
+        "Unknown reason 0x00010009 (sub-system: SKB_DROP_REASON_SUBSYS_MAC80211_UNUSABLE, index: 9; nearest known: 0x00010002=RX_DROP_U_REPLAY)"
+    );
+}